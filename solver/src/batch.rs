@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+
+use crate::solve_input;
+use crate::types::*;
+
+/// Width of the enhancement level buckets in the alliance summary.
+const LEVEL_BUCKET: i32 = 20;
+
+/// Solves every player on their own and sums up the players that solved. A
+/// player whose gain rises with `mythic_step` more mythics counts as held
+/// back by mythics.
+pub fn run(batch: BatchInput) -> BatchOutput {
+    let mut summary = AllianceSummary::default();
+    // Bucket index -> (current, recommended) piece counts.
+    let mut buckets: BTreeMap<i32, (usize, usize)> = BTreeMap::new();
+    let mut players = Vec::with_capacity(batch.players.len());
+
+    for player in batch.players {
+        let result = solve_input(player.input.clone());
+        match &result {
+            Ok(output) => {
+                summary.solved += 1;
+                summary.total_before_score += output.total_before_score;
+                summary.total_after_score += output.total_after_score;
+                if let Some(score_gain) = mythic_gain(&player.input, output, batch.mythic_step) {
+                    summary.mythic_blocked.push(MythicBlock { name: player.name.clone(), score_gain });
+                }
+                for gear in output.results.iter().flat_map(|result| &result.gear) {
+                    buckets.entry(gear.current_enhancement / LEVEL_BUCKET).or_default().0 += 1;
+                    buckets.entry(gear.recommended_enhancement / LEVEL_BUCKET).or_default().1 += 1;
+                }
+            }
+            Err(_) => summary.failed.push(player.name.clone()),
+        }
+        players.push(PlayerOutput { name: player.name, result: result.into() });
+    }
+
+    summary.total_score_gain = summary.total_after_score - summary.total_before_score;
+    summary.mythic_blocked.sort_by(|a, b| b.score_gain.total_cmp(&a.score_gain));
+    if let Some(&last) = buckets.keys().next_back() {
+        summary.enhancement_levels = (0..=last)
+            .map(|bucket| {
+                let (current, recommended) = buckets.get(&bucket).copied().unwrap_or_default();
+                let from = bucket * LEVEL_BUCKET;
+                LevelBucket { from, to: from + LEVEL_BUCKET - 1, current, recommended }
+            })
+            .collect();
+    }
+    BatchOutput { players, summary }
+}
+
+/// What `step` more mythics would add to the player's score, if anything.
+fn mythic_gain(input: &InputData, output: &OptimizationOutput, step: i32) -> Option<f64> {
+    if step <= 0 {
+        return None;
+    }
+    let mut more = input.clone();
+    more.mythics = more.mythics.saturating_add(step);
+    more.shadow_prices = None;
+    more.pareto = None;
+    let gain = solve_input(more).ok()?.total_after_score - output.total_after_score;
+    (gain > 1e-9 * output.total_after_score.abs().max(1.0)).then_some(gain)
+}
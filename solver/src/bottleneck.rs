@@ -0,0 +1,119 @@
+use std::cmp::Reverse;
+
+use crate::model::{enhancement_cost, mastery_cost};
+use crate::types::*;
+
+/// Names the next step every piece of `output` could not take, with what it
+/// lacks, and ranks the resources by how many such steps they hold back.
+/// Pieces at their caps, locked ones and steps the plan could still afford
+/// are left out. `input` is the input that was solved.
+pub fn explain(input: &InputData, output: &OptimizationOutput) -> (Vec<Bottleneck>, Vec<ResourcePressure>) {
+    let game_data = input.game_data();
+    let left = output.leftover;
+    let mut bottlenecks = Vec::new();
+    for (hero, result) in input.heroes.iter().zip(&output.results) {
+        for (gear, piece) in game_data.pieces(&hero.gear).into_iter().zip(&result.gear) {
+            if gear.locked {
+                continue;
+            }
+            let tables = game_data.tables_for(gear);
+            let (enhancement, mastery) = (piece.recommended_enhancement, piece.recommended_mastery);
+            let max_enhancement = gear.max_enhancement.map_or(tables.max_enhancement, |cap| cap.min(tables.max_enhancement));
+            let max_mastery = gear.target_mastery.map_or(tables.max_mastery, |target| target.max(gear.mastery));
+            let step = |action, from, to, cost: Resources, required_mastery| Bottleneck {
+                hero_name: result.hero_name.clone(),
+                gear_type: piece.gear_type.clone(),
+                action,
+                from,
+                to,
+                cost,
+                missing: shortfall(cost, left),
+                required_mastery,
+                message: String::new(),
+            };
+
+            let blocked = if enhancement < max_enhancement {
+                let next = enhancement + 1;
+                let required = tables.required_mastery(next);
+                let prerequisite = mastery_cost(tables, mastery, mastery.max(required));
+                let cost = enhancement_cost(tables, enhancement, next) + prerequisite;
+                let mut blocked = step(PlanAction::Enhance, enhancement, next, cost, (required > mastery).then_some(required));
+                let forbidden = required > max_mastery;
+                if blocked.missing == Resources::default() && !forbidden {
+                    continue;
+                }
+                let mut parts = needs(&blocked.missing, false);
+                if forbidden {
+                    parts.push(format!("mastery {}, above targetMastery {}", required, max_mastery));
+                } else if required > mastery {
+                    parts.push(format!("mastery {} (costs {})", required, amounts(&prerequisite)));
+                }
+                blocked.message = format!("blocked at {}→{}: need {}", enhancement, next, parts.join(" + "));
+                blocked
+            } else if mastery < max_mastery {
+                let cost = mastery_cost(tables, mastery, mastery + 1);
+                let mut blocked = step(PlanAction::Mastery, mastery, mastery + 1, cost, None);
+                if blocked.missing == Resources::default() {
+                    continue;
+                }
+                let parts = needs(&blocked.missing, true);
+                blocked.message = format!("blocked at mastery {}→{}: need {}", mastery, mastery + 1, parts.join(" + "));
+                blocked
+            } else {
+                continue;
+            };
+            bottlenecks.push(blocked);
+        }
+    }
+
+    let by_kind = |r: &Resources| [r.exp, r.hammers, r.mythics, r.mythril];
+    let mut ranking: Vec<ResourcePressure> = ["exp", "hammers", "mythics", "mythril"]
+        .into_iter()
+        .enumerate()
+        .map(|(k, resource)| {
+            let short: Vec<i32> = bottlenecks.iter().map(|b| by_kind(&b.missing)[k]).filter(|&m| m > 0).collect();
+            let missing = short.iter().fold(0, |total: i32, &m| total.saturating_add(m));
+            ResourcePressure { resource: resource.to_string(), blocked: short.len(), missing }
+        })
+        .filter(|pressure| pressure.blocked > 0)
+        .collect();
+    // Stable, so equal counts keep the order above.
+    ranking.sort_by_key(|pressure| Reverse(pressure.blocked));
+    (bottlenecks, ranking)
+}
+
+fn shortfall(cost: Resources, left: Resources) -> Resources {
+    Resources {
+        exp: (cost.exp - left.exp).max(0),
+        hammers: (cost.hammers - left.hammers).max(0),
+        mythics: (cost.mythics - left.mythics).max(0),
+        mythril: (cost.mythril - left.mythril).max(0),
+    }
+}
+
+/// The missing amounts, e.g. `["2 mythics"]`. Missing hammers are left to
+/// the mastery prerequisite unless `hammers` is set.
+fn needs(missing: &Resources, hammers: bool) -> Vec<String> {
+    let mut needs = Vec::new();
+    for (amount, name) in [
+        (missing.exp, "exp"),
+        (if hammers { missing.hammers } else { 0 }, "hammers"),
+        (missing.mythics, "mythics"),
+        (missing.mythril, "mythril"),
+    ] {
+        if amount > 0 {
+            needs.push(format!("{} {}", amount, name));
+        }
+    }
+    needs
+}
+
+/// The non-zero amounts of `cost`, e.g. `550 hammers, 3 mythics`.
+fn amounts(cost: &Resources) -> String {
+    let parts: Vec<String> = [(cost.exp, "exp"), (cost.hammers, "hammers"), (cost.mythics, "mythics"), (cost.mythril, "mythril")]
+        .into_iter()
+        .filter(|&(amount, _)| amount > 0)
+        .map(|(amount, name)| format!("{} {}", amount, name))
+        .collect();
+    parts.join(", ")
+}
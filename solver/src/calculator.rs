@@ -0,0 +1,56 @@
+use crate::error::SolverError;
+use crate::model::{enhancement_cost, mastery_cost};
+use crate::types::*;
+
+/// The exact cost of taking a piece from `from` to `to`, both of the same
+/// tier. When `to` raises the enhancement, its mastery is raised to what the
+/// new level requires and paid for too. Error fields are `from.*` and `to.*`.
+pub fn cost_between(game_data: &GameData, from: &Gear, to: &Gear) -> Result<TransitionCost, SolverError> {
+    let Some(tier) = game_data.tier_rank(from.tier.as_deref()) else {
+        return Err(SolverError::validation(
+            "from.tier",
+            format!("unknown tier `{}`", from.tier.as_deref().unwrap_or_default()),
+        ));
+    };
+    if game_data.tier_rank(to.tier.as_deref()) != Some(tier) {
+        return Err(SolverError::validation("to.tier", "must be the tier of `from`"));
+    }
+    let game_data = game_data.tables(tier);
+    for (name, gear) in [("from", from), ("to", to)] {
+        if !(0..=game_data.max_enhancement).contains(&gear.enhancement) {
+            return Err(SolverError::validation(
+                format!("{}.enhancement", name),
+                format!("{} is outside 0..={}", gear.enhancement, game_data.max_enhancement),
+            ));
+        }
+        if !(0..=game_data.max_mastery).contains(&gear.mastery) {
+            return Err(SolverError::validation(
+                format!("{}.mastery", name),
+                format!("{} is outside 0..={}", gear.mastery, game_data.max_mastery),
+            ));
+        }
+    }
+    if to.enhancement < from.enhancement {
+        return Err(SolverError::validation("to.enhancement", format!("must be at least {}", from.enhancement)));
+    }
+    if to.mastery < from.mastery {
+        return Err(SolverError::validation("to.mastery", format!("must be at least {}", from.mastery)));
+    }
+
+    let mastery = if to.enhancement > from.enhancement {
+        to.mastery.max(game_data.required_mastery(to.enhancement))
+    } else {
+        to.mastery
+    };
+    Ok(TransitionCost {
+        cost: enhancement_cost(game_data, from.enhancement, to.enhancement)
+            + mastery_cost(game_data, from.mastery, mastery),
+        mastery,
+        gates: game_data
+            .gates
+            .iter()
+            .filter(|gate| gate.level > from.enhancement && gate.level <= to.enhancement)
+            .cloned()
+            .collect(),
+    })
+}
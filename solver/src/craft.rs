@@ -0,0 +1,163 @@
+use crate::greedy::solve_greedy;
+use crate::types::*;
+
+/// One piece crafted into a higher tier.
+#[derive(Clone, Debug)]
+pub struct Craft {
+    hero: usize,
+    slot: usize,
+    tier: usize,
+    cost: Resources,
+}
+
+/// Every affordable craft: each piece that may be given up (neither locked
+/// nor `noReset`), into each higher tier with a `craftCost`.
+fn candidates(input: &InputData) -> Vec<Craft> {
+    let game_data = input.game_data();
+    let budget = input.budget();
+    let mut out = Vec::new();
+    for (hero, weights) in input.heroes.iter().enumerate() {
+        for (slot, gear) in game_data.pieces(&weights.gear).into_iter().enumerate() {
+            let from = game_data.tier_rank(gear.tier.as_deref()).unwrap_or(game_data.top_tier());
+            if gear.locked || gear.no_reset {
+                continue;
+            }
+            for tier in (from + 1)..=game_data.top_tier() {
+                let Some(cost) = game_data.tables(tier).craft_cost else {
+                    continue;
+                };
+                if (budget - cost).is_non_negative() {
+                    out.push(Craft { hero, slot, tier, cost });
+                }
+            }
+        }
+    }
+    out
+}
+
+/// `input` with the crafted pieces replaced by new ones at 0 and their cost
+/// taken from the budget. The new piece keeps the player's constraints.
+pub fn apply_crafts(input: &InputData, crafts: &[Craft]) -> InputData {
+    let game_data = input.game_data();
+    let mut crafted = input.clone();
+    let mut cost = Resources::default();
+    for craft in crafts {
+        let gear = game_data.pieces_mut(&mut crafted.heroes[craft.hero].gear).swap_remove(craft.slot);
+        gear.tier = game_data.tables(craft.tier).tier.clone();
+        gear.enhancement = 0;
+        gear.mastery = 0;
+        cost += craft.cost;
+    }
+    crafted.set_budget(input.budget() - cost);
+    crafted
+}
+
+/// Decides which pieces to craft into a higher tier. Like shop purchases,
+/// crafts are scored by the greedy solver on the whole roster: each round
+/// takes the craft that raises the score most, until none does. Every
+/// candidate is also reported with the score of crafting it alone.
+pub fn plan_crafts(input: &InputData) -> (Vec<Craft>, Vec<CraftOption>) {
+    let score = |crafts: &[Craft]| -> Option<f64> {
+        let mut trial = apply_crafts(input, crafts);
+        if !trial.budget().is_non_negative() {
+            return None;
+        }
+        trial.allow_crafting = false;
+        trial.shadow_prices = None;
+        trial.pareto = None;
+        trial.strategy = Strategy::Greedy;
+        Some(solve_greedy(trial).total_after_score)
+    };
+    let tolerance = |score: f64| 1e-9 * score.abs().max(1.0);
+
+    let candidates = candidates(input);
+    let enhance_score = score(&[]).unwrap_or_default();
+    let mut chosen: Vec<Craft> = Vec::new();
+    let mut best = enhance_score;
+    loop {
+        let mut best_move: Option<(usize, f64)> = None;
+        for (i, craft) in candidates.iter().enumerate() {
+            if chosen.iter().any(|c| (c.hero, c.slot) == (craft.hero, craft.slot)) {
+                continue;
+            }
+            let mut trial = chosen.clone();
+            trial.push(craft.clone());
+            let Some(trial_score) = score(&trial) else {
+                continue;
+            };
+            if trial_score > best + tolerance(best) && best_move.is_none_or(|(_, s)| trial_score > s) {
+                best_move = Some((i, trial_score));
+            }
+        }
+        match best_move {
+            Some((i, trial_score)) => {
+                chosen.push(candidates[i].clone());
+                best = trial_score;
+            }
+            None => break,
+        }
+    }
+
+    let game_data = input.game_data();
+    let options = candidates
+        .iter()
+        .map(|craft| {
+            let gear = game_data.pieces(&input.heroes[craft.hero].gear)[craft.slot];
+            CraftOption {
+                hero_name: input.heroes[craft.hero].name.clone(),
+                gear_type: game_data.gear_set.slots[craft.slot].name.clone(),
+                from_tier: gear.tier.clone(),
+                to_tier: tier_name(game_data, craft.tier),
+                cost: craft.cost,
+                enhance_score,
+                craft_score: score(std::slice::from_ref(craft)).unwrap_or_default(),
+                chosen: chosen.iter().any(|c| (c.hero, c.slot, c.tier) == (craft.hero, craft.slot, craft.tier)),
+            }
+        })
+        .collect();
+    (chosen, options)
+}
+
+fn tier_name(game_data: &GameData, tier: usize) -> String {
+    game_data.tables(tier).tier.clone().unwrap_or_default()
+}
+
+/// Turns an output solved for the crafted input back into one for
+/// `input`: crafted pieces start from the player's piece, carry the craft
+/// in their spending, and the plan starts with the crafts.
+pub fn report_crafts(input: &InputData, crafts: &[Craft], output: &mut OptimizationOutput) {
+    if crafts.is_empty() {
+        return;
+    }
+    let game_data = input.game_data();
+    let mut balance = input.budget();
+    let mut steps = Vec::with_capacity(crafts.len() + output.plan.len());
+    for craft in crafts {
+        let hero = &input.heroes[craft.hero];
+        let old = game_data.pieces(&hero.gear)[craft.slot];
+        let result = &mut output.results[craft.hero];
+        let piece = &mut result.gear[craft.slot];
+        piece.current_enhancement = old.enhancement;
+        piece.current_mastery = old.mastery;
+        piece.crafted = Some(tier_name(game_data, craft.tier));
+        piece.spent += craft.cost;
+        result.spent += craft.cost;
+        result.before_stats = game_data.calculate_stats(&hero.gear, &hero.set_bonuses);
+        result.before_score = input.objective.hero_value(&hero.weights, &result.before_stats);
+        output.used += craft.cost;
+        balance -= craft.cost;
+        steps.push(PlanStep {
+            action: PlanAction::Craft,
+            hero_name: hero.name.clone(),
+            gear_type: piece.gear_type.clone(),
+            from: old.enhancement,
+            to: 0,
+            cost: craft.cost,
+            refund: Resources::default(),
+            balance,
+        });
+    }
+    output.total_before_score = input.objective.combine(output.results.iter().map(|result| result.before_score));
+    steps.append(&mut output.plan);
+    output.plan = steps;
+}
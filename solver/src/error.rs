@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::OptimizationOutput;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorKind {
+    /// The input is not valid JSON or does not match the input schema.
+    Parse,
+    /// The input parsed but holds values the solver cannot work with.
+    Validation,
+    /// The solver itself failed.
+    Internal,
+}
+
+/// Why `solve` produced no output. `field` is the JSON path of the offending
+/// value, such as `heroes[0].gear.helmet.mastery`, when there is one.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SolverError {
+    pub kind: ErrorKind,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl SolverError {
+    pub fn parse(field: Option<String>, message: impl Into<String>) -> Self {
+        SolverError { kind: ErrorKind::Parse, field, message: message.into() }
+    }
+
+    pub fn validation(field: impl Into<String>, message: impl Into<String>) -> Self {
+        SolverError { kind: ErrorKind::Validation, field: Some(field.into()), message: message.into() }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        SolverError { kind: ErrorKind::Internal, field: None, message: message.into() }
+    }
+
+    /// Moves the error's field under `prefix`, for errors found in a nested
+    /// object.
+    pub fn within(mut self, prefix: &str) -> Self {
+        self.field = Some(match self.field {
+            Some(field) if field.starts_with('[') => format!("{}{}", prefix, field),
+            Some(field) => format!("{}.{}", prefix, field),
+            None => prefix.to_string(),
+        });
+        self
+    }
+}
+
+impl std::fmt::Display for SolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "{}: {}", field, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// What `solve` returns: `{"ok": <output>}` or `{"error": {kind, field, message}}`.
+/// `solve_batch` wraps its `BatchOutput` the same way.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum SolveResult<T = OptimizationOutput> {
+    Ok(Box<T>),
+    Error(SolverError),
+}
+
+impl<T> From<Result<T, SolverError>> for SolveResult<T> {
+    fn from(result: Result<T, SolverError>) -> Self {
+        match result {
+            Ok(output) => SolveResult::Ok(Box::new(output)),
+            Err(error) => SolveResult::Error(error),
+        }
+    }
+}
+
+impl<T> From<SolveResult<T>> for Result<T, SolverError> {
+    fn from(result: SolveResult<T>) -> Self {
+        match result {
+            SolveResult::Ok(output) => Ok(*output),
+            SolveResult::Error(error) => Err(error),
+        }
+    }
+}
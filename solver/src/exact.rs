@@ -0,0 +1,460 @@
+use crate::greedy::{raise_to_floors, run_greedy};
+use crate::model::*;
+use crate::objective::GainBounds;
+use crate::plan::*;
+use crate::types::*;
+
+/// Branch-and-bound nodes explored before the search stops and returns the
+/// best allocation found so far without an optimality proof.
+const NODE_LIMIT: u64 = 5_000_000;
+
+/// Subgradient iterations used to tune the Lagrangian multipliers.
+const SUBGRADIENT_ITERATIONS: usize = 200;
+
+/// Limits on the knapsack table over the binding resource: its finest
+/// resolution, the work spent filling it and its size.
+const MAX_BUCKETS: usize = 10_000;
+const TABLE_WORK: usize = 50_000_000;
+const TABLE_CELLS: usize = 2_000_000;
+
+/// One final state a piece can be taken to, with its cost in the order
+/// exp, hammers, mythics, mythril.
+struct Choice {
+    enhancement: i32,
+    mastery: i32,
+    gain: f64,
+    cost: [i64; 4],
+    /// Gain less the cost priced by all four multipliers.
+    reduced: f64,
+    /// Gain less the cost in the resources the suffix bound does not solve
+    /// for.
+    reduced_others: f64,
+}
+
+fn to_array(r: Resources) -> [i64; 4] {
+    [r.exp as i64, r.hammers as i64, r.mythics as i64, r.mythril as i64]
+}
+
+/// Every affordable final `(enhancement, mastery)` for the item, the
+/// untouched state first. Gains are filled in by the caller.
+fn choices(game_data: &GameData, item: &OptimizationItem, budget: &[i64; 4]) -> Vec<Choice> {
+    let game_data = item.tables(game_data);
+    let mut out = Vec::new();
+
+    for mastery in item.mastery..=item.max_mastery {
+        for enhancement in item.current_enhancement..=item.max_enhancement {
+            let Some(cost) = upgrade_cost(game_data, item.current_enhancement, item.mastery, enhancement, mastery) else {
+                continue;
+            };
+            let cost = to_array(cost);
+            if cost[0] > budget[0] {
+                // EXP only grows with enhancement, so higher levels are out of reach too.
+                break;
+            }
+            if cost.iter().zip(budget).any(|(c, b)| c > b) {
+                continue;
+            }
+            out.push(Choice { enhancement, mastery, gain: 0.0, cost, reduced: 0.0, reduced_others: 0.0 });
+        }
+    }
+    out
+}
+
+/// Multipliers are applied to costs scaled by the budget so that EXP and
+/// mythics are on comparable scales.
+fn scaled(cost: &[i64; 4], budget: &[i64; 4]) -> [f64; 4] {
+    let mut out = [0.0; 4];
+    for r in 0..4 {
+        out[r] = cost[r] as f64 / budget[r].max(1) as f64;
+    }
+    out
+}
+
+fn dot(a: &[f64; 4], b: &[f64; 4]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Finds multipliers for the Lagrangian relaxation
+/// `L(λ) = λ·B + Σ max(gain - λ·cost)`, which bounds the optimum from above
+/// for every `λ >= 0`. Uses Polyak subgradient steps towards `lower_bound`.
+fn tune_multipliers(pieces: &[Vec<Choice>], budget: &[i64; 4], lower_bound: f64) -> [f64; 4] {
+    let scaled_budget = scaled(budget, budget);
+    let mut lambda = [0.0; 4];
+    let mut best_lambda = lambda;
+    let mut best_bound = f64::INFINITY;
+    let mut step = 2.0;
+    let mut stalled = 0;
+
+    for _ in 0..SUBGRADIENT_ITERATIONS {
+        let mut bound = dot(&lambda, &scaled_budget);
+        let mut used = [0.0; 4];
+        for options in pieces {
+            let mut best = f64::NEG_INFINITY;
+            let mut best_cost = [0.0; 4];
+            for choice in options {
+                let c = scaled(&choice.cost, budget);
+                let value = choice.gain - dot(&lambda, &c);
+                if value > best {
+                    best = value;
+                    best_cost = c;
+                }
+            }
+            bound += best;
+            for r in 0..4 {
+                used[r] += best_cost[r];
+            }
+        }
+
+        if bound < best_bound - 1e-12 {
+            best_bound = bound;
+            best_lambda = lambda;
+            stalled = 0;
+        } else {
+            stalled += 1;
+            if stalled >= 10 {
+                step /= 2.0;
+                stalled = 0;
+            }
+        }
+
+        let subgradient: Vec<f64> = (0..4).map(|r| scaled_budget[r] - used[r]).collect();
+        let norm: f64 = subgradient.iter().map(|g| g * g).sum();
+        let gap = bound - lower_bound;
+        if norm < 1e-18 || gap <= 1e-12 {
+            break;
+        }
+        let t = step * gap / norm;
+        for r in 0..4 {
+            lambda[r] = (lambda[r] - t * subgradient[r]).max(0.0);
+        }
+    }
+    best_lambda
+}
+
+/// What the pieces from a depth on can add at most, bounded two ways: by
+/// the Lagrangian relaxation of all four resources, and as a knapsack in the
+/// binding resource, with its costs rounded down to whole buckets, priced by
+/// the multipliers in the other three.
+struct SuffixBound {
+    /// Sum of the best reduced profits of pieces `d..`.
+    relaxed: Vec<f64>,
+    resource: usize,
+    unit: i64,
+    /// `table[d][x]` is the most the pieces `d..` reach in `reduced_others`
+    /// with `x` buckets of the resource.
+    table: Vec<Vec<f64>>,
+}
+
+impl SuffixBound {
+    fn new(pieces: &[Vec<Choice>], resource: usize, available: i64) -> Self {
+        // Per piece, only the choices that cost more to gain more matter.
+        let fronts = |unit: i64| -> Vec<Vec<(usize, f64)>> {
+            pieces
+                .iter()
+                .map(|options| {
+                    let mut front: Vec<(usize, f64)> =
+                        options.iter().map(|c| ((c.cost[resource] / unit) as usize, c.reduced_others)).collect();
+                    front.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.total_cmp(&a.1)));
+                    let mut best = f64::NEG_INFINITY;
+                    front.retain(|&(_, reduced)| {
+                        let better = reduced > best;
+                        best = best.max(reduced);
+                        better
+                    });
+                    front
+                })
+                .collect()
+        };
+        let work = fronts(1).iter().map(Vec::len).sum::<usize>().max(1);
+        let buckets = MAX_BUCKETS.min(TABLE_WORK / work).min(TABLE_CELLS / (pieces.len() + 1)).max(1) as i64;
+        let unit = ((available + buckets - 1) / buckets).max(1);
+        let capacity = (available / unit) as usize;
+
+        let mut table = vec![vec![0.0; capacity + 1]; pieces.len() + 1];
+        for (depth, front) in fronts(unit).iter().enumerate().rev() {
+            let (rows, rest) = table.split_at_mut(depth + 1);
+            let (row, next) = (&mut rows[depth], &rest[0]);
+            for (x, cell) in row.iter_mut().enumerate() {
+                // Every piece can stay as it is for free, so the front
+                // starts at 0 and the cell is always reached.
+                *cell = front
+                    .iter()
+                    .take_while(|&&(w, _)| w <= x)
+                    .map(|&(w, reduced)| reduced + next[x - w])
+                    .fold(f64::NEG_INFINITY, f64::max);
+            }
+        }
+        let mut relaxed = vec![0.0; pieces.len() + 1];
+        for depth in (0..pieces.len()).rev() {
+            relaxed[depth] = relaxed[depth + 1] + pieces[depth][0].reduced;
+        }
+        SuffixBound { relaxed, resource, unit, table }
+    }
+
+    /// The knapsack bound for the pieces `depth..` with `remaining` left.
+    fn knapsack(&self, depth: usize, remaining: &[i64; 4]) -> f64 {
+        self.table[depth][(remaining[self.resource] / self.unit) as usize]
+    }
+}
+
+/// How the objective gain of an assignment is found.
+#[derive(Clone, Copy)]
+enum Scoring<'a> {
+    /// The sum of the choices' gains, which are exact.
+    Sum,
+    /// Hero by hero: the gain of the hero whose last piece is at the given
+    /// depth, replacing the bounds of its pieces once they are all chosen.
+    Heroes(&'a dyn Fn(usize, &[usize]) -> f64),
+    /// Only as a whole, once every piece is chosen.
+    Whole(&'a dyn Fn(&[usize]) -> f64),
+}
+
+struct Search<'a> {
+    pieces: &'a [Vec<Choice>],
+    suffix: SuffixBound,
+    lambda: [f64; 4],
+    /// `lambda` without the resource the knapsack bound solves for.
+    lambda_others: [f64; 4],
+    /// Part of the bound that no piece accounts for.
+    offset: f64,
+    budget: [i64; 4],
+    scoring: Scoring<'a>,
+    /// Whether the piece at each depth is the last of its hero.
+    hero_end: Vec<bool>,
+    current: Vec<usize>,
+    best: Option<Vec<usize>>,
+    best_gain: f64,
+    nodes: u64,
+}
+
+impl Search<'_> {
+    /// `settled` is the exact gain of the pieces chosen so far, and `partial`
+    /// the bound on those whose gain is not known yet.
+    fn run(&mut self, depth: usize, settled: f64, partial: f64, remaining: [i64; 4]) {
+        if depth == self.pieces.len() {
+            let gain = match self.scoring {
+                Scoring::Whole(value) => value(&self.current),
+                _ => settled + partial,
+            };
+            if gain > self.best_gain + tolerance(self.best_gain) {
+                self.best_gain = gain;
+                self.best = Some(self.current.clone());
+            }
+            return;
+        }
+
+        let known = self.offset + settled + partial;
+        let slack = dot(&self.lambda, &scaled(&remaining, &self.budget));
+        let others_slack = dot(&self.lambda_others, &scaled(&remaining, &self.budget));
+        for (i, choice) in self.pieces[depth].iter().enumerate() {
+            if self.nodes >= NODE_LIMIT {
+                return;
+            }
+            let bound = known + slack + choice.reduced + self.suffix.relaxed[depth + 1];
+            if bound <= self.best_gain + tolerance(self.best_gain) {
+                // Choices are sorted by reduced profit, so the rest cannot do better.
+                break;
+            }
+            if choice.cost.iter().zip(&remaining).any(|(c, r)| c > r) {
+                continue;
+            }
+            let mut next = remaining;
+            for (n, c) in next.iter_mut().zip(&choice.cost) {
+                *n -= c;
+            }
+            let knapsack = known + others_slack + choice.reduced_others + self.suffix.knapsack(depth + 1, &next);
+            if knapsack <= self.best_gain + tolerance(self.best_gain) {
+                continue;
+            }
+            self.nodes += 1;
+            self.current[depth] = i;
+            match self.scoring {
+                Scoring::Sum => self.run(depth + 1, settled + choice.gain, partial, next),
+                Scoring::Heroes(hero_gain) if self.hero_end[depth] => {
+                    let gain = hero_gain(depth, &self.current);
+                    self.run(depth + 1, settled + gain, 0.0, next)
+                }
+                _ => self.run(depth + 1, settled, partial + choice.gain, next),
+            }
+        }
+    }
+}
+
+fn tolerance(value: f64) -> f64 {
+    1e-9 * value.abs().max(1.0)
+}
+
+/// Finds the allocation with the highest objective value for the same budget
+/// and reset rules as `solve_greedy`, and reports how far the greedy
+/// allocation falls short of it.
+///
+/// Every piece is treated as a choice among its reachable final states. A
+/// depth-first branch-and-bound, seeded with the greedy allocation, prunes
+/// with a Lagrangian bound over the four resources and a knapsack over the
+/// one that binds hardest. That proves optimality for a hero or two; larger
+/// rosters usually hit `NODE_LIMIT`, and the best allocation found is
+/// returned with `proven_optimal` unset and the root bound. Objectives
+/// other than `Linear` are not a sum over pieces, so pruning uses the
+/// objective's looser bounds until a hero, or under `MaxMin` the whole
+/// roster, is complete and can be valued exactly.
+pub fn solve_exact(input: InputData) -> OptimizationOutput {
+    let game_data = input.game_data();
+    let objective = input.objective;
+    let (mut items, budget) = prepare_items(&input);
+    let start = items.clone();
+    let (floor_upgrades, budget) = raise_to_floors(game_data, &mut items, budget);
+    let floors = items.clone();
+    let start_score = total_score(objective, game_data, &items);
+
+    let mut greedy_items = floors.clone();
+    run_greedy(game_data, objective, &mut greedy_items, budget);
+    let greedy_score = total_score(objective, game_data, &greedy_items);
+    let greedy_gain = greedy_score - start_score;
+
+    let budget = to_array(budget).map(|b| b.max(0));
+    let mut pieces: Vec<(usize, Vec<Choice>)> =
+        floors.iter().map(|item| choices(game_data, item, &budget)).enumerate().collect();
+    // No piece gets past what the budget alone allows, which tightens the
+    // objective's bounds.
+    let mut reach = floors.clone();
+    for ((_, options), item) in pieces.iter().zip(&mut reach) {
+        item.max_enhancement = options.iter().map(|c| c.enhancement).max().unwrap_or(item.current_enhancement);
+        item.max_mastery = options.iter().map(|c| c.mastery).max().unwrap_or(item.mastery);
+    }
+    let bounds = GainBounds::new(objective, game_data, &reach);
+    let set_bonuses = has_set_bonuses(game_data, &floors);
+    for (i, options) in &mut pieces {
+        for choice in options.iter_mut() {
+            choice.gain = bounds.gain(*i, choice.enhancement, choice.mastery);
+        }
+        // A piece that adds nothing itself may still earn a set bonus.
+        if !set_bonuses {
+            let item = &floors[*i];
+            options.retain(|c| (c.enhancement == item.current_enhancement && c.mastery == item.mastery) || c.gain > 0.0);
+        }
+    }
+    pieces.retain(|(_, options)| options.len() > 1);
+
+    let options: Vec<Vec<Choice>> = pieces.iter_mut().map(|(_, o)| std::mem::take(o)).collect();
+    let lambda = tune_multipliers(&options, &budget, greedy_gain);
+    // The resource with the highest multiplier binds hardest; the knapsack
+    // bound solves for it instead of pricing it.
+    let binding = (0..4).max_by(|&a, &b| lambda[a].total_cmp(&lambda[b])).unwrap_or(0);
+    let mut lambda_others = lambda;
+    lambda_others[binding] = 0.0;
+    for ((_, slot), mut piece_options) in pieces.iter_mut().zip(options) {
+        for choice in &mut piece_options {
+            let cost = scaled(&choice.cost, &budget);
+            choice.reduced = choice.gain - dot(&lambda, &cost);
+            choice.reduced_others = choice.gain - dot(&lambda_others, &cost);
+        }
+        piece_options.sort_by(|a, b| b.reduced.total_cmp(&a.reduced));
+        *slot = piece_options;
+    }
+    // Branch on the pieces with the most to gain first. When heroes are
+    // scored one at a time their pieces stay together, heroes with the most
+    // to gain first.
+    let slots = game_data.slot_count();
+    let hero_by_hero = match objective {
+        Objective::Linear => set_bonuses,
+        Objective::Multiplicative | Objective::LogUtility => true,
+        Objective::MaxMin => false,
+    };
+    let mut hero_potential = vec![0.0; floors.len() / slots];
+    for (i, piece_options) in &pieces {
+        hero_potential[i / slots] += piece_options[0].reduced;
+    }
+    if hero_by_hero {
+        pieces.sort_by(|(i, a), (j, b)| {
+            hero_potential[j / slots]
+                .total_cmp(&hero_potential[i / slots])
+                .then((i / slots).cmp(&(j / slots)))
+                .then(b[0].reduced.total_cmp(&a[0].reduced))
+        });
+    } else {
+        pieces.sort_by(|(_, a), (_, b)| b[0].reduced.total_cmp(&a[0].reduced));
+    }
+    let (order, options): (Vec<usize>, Vec<Vec<Choice>>) = pieces.into_iter().unzip();
+
+    let suffix = SuffixBound::new(&options, binding, budget[binding]);
+
+    let offset = bounds.offset;
+    let hero_end: Vec<bool> =
+        (0..order.len()).map(|d| order.get(d + 1).is_none_or(|next| next / slots != order[d] / slots)).collect();
+    let hero_gain = |last: usize, current: &[usize]| {
+        let hero = order[last] / slots;
+        let own = &floors[hero * slots..(hero + 1) * slots];
+        let mut trial = own.to_vec();
+        for depth in (0..=last).rev().take_while(|&depth| order[depth] / slots == hero) {
+            let choice = &options[depth][current[depth]];
+            trial[order[depth] % slots].current_enhancement = choice.enhancement;
+            trial[order[depth] % slots].mastery = choice.mastery;
+        }
+        objective.value(game_data, &trial) - objective.value(game_data, own)
+    };
+    let evaluate = |current: &[usize]| {
+        let mut trial = floors.clone();
+        for ((&item_idx, piece_options), &choice_idx) in order.iter().zip(&options).zip(current) {
+            trial[item_idx].current_enhancement = piece_options[choice_idx].enhancement;
+            trial[item_idx].mastery = piece_options[choice_idx].mastery;
+        }
+        total_score(objective, game_data, &trial) - start_score
+    };
+    let mut search = Search {
+        pieces: &options,
+        suffix,
+        lambda,
+        lambda_others,
+        offset,
+        budget,
+        scoring: match objective {
+            Objective::MaxMin => Scoring::Whole(&evaluate),
+            _ if hero_by_hero => Scoring::Heroes(&hero_gain),
+            _ => Scoring::Sum,
+        },
+        hero_end,
+        current: vec![0; options.len()],
+        best: None,
+        best_gain: greedy_gain,
+        nodes: 0,
+    };
+    search.run(0, 0.0, 0.0, budget);
+    let proven_optimal = search.nodes < NODE_LIMIT;
+    let scaled_budget = scaled(&budget, &budget);
+    let root_bound = offset
+        + (search.suffix.relaxed[0] + dot(&lambda, &scaled_budget))
+            .min(search.suffix.knapsack(0, &budget) + dot(&lambda_others, &scaled_budget));
+
+    match search.best {
+        Some(best) => {
+            for ((&item_idx, piece_options), &choice_idx) in order.iter().zip(&options).zip(&best) {
+                let choice = &piece_options[choice_idx];
+                items[item_idx].current_enhancement = choice.enhancement;
+                items[item_idx].mastery = choice.mastery;
+            }
+        }
+        // Nothing beats the greedy allocation, so it is optimal.
+        None => items = greedy_items,
+    }
+
+    let mut output = build_output(&input, &start, &items);
+    let mut upgrades = floor_upgrades;
+    upgrades.extend(upgrades_between(game_data, &floors, &items));
+    output.plan = build_plan(&input, &start, &upgrades);
+    let optimal_score = output.total_after_score;
+    let absolute_gap = (optimal_score - greedy_score).max(0.0);
+    output.greedy_gap = Some(GreedyGap {
+        greedy_score,
+        optimal_score,
+        absolute_gap,
+        relative_gap: if optimal_score > 0.0 { absolute_gap / optimal_score } else { 0.0 },
+        upper_bound: if proven_optimal {
+            optimal_score
+        } else {
+            (start_score + root_bound).max(optimal_score)
+        },
+        proven_optimal,
+    });
+    output
+}
+
@@ -0,0 +1,176 @@
+use crate::types::*;
+
+type Feeds = Vec<((usize, usize), ExpFeed)>;
+
+/// Solves with EXP materials instead of a freely divisible EXP pool. The
+/// materials are first counted as loose EXP; when the resulting plan cannot
+/// be fed with whole materials, the EXP budget is lowered by the shortfall
+/// and the input solved again. A material that overshoots a piece's target
+/// still counts towards its next levels, up to the next gate, mastery
+/// requirement or cap; only what is left beyond that is reported as wasted.
+pub fn solve_with_items(input: InputData, solve: impl Fn(InputData) -> OptimizationOutput) -> OptimizationOutput {
+    let items_exp =
+        input.exp_items.iter().fold(0i32, |total, stack| total.saturating_add(stack.exp.saturating_mul(stack.count)));
+    let full = input.exp.saturating_add(items_exp);
+    let mut allowance = 0;
+    let (trial, output, mut feeds, leftover) = loop {
+        let mut trial = input.clone();
+        trial.exp_items = Vec::new();
+        trial.exp = (full - allowance).max(input.exp);
+        let output = solve(trial.clone());
+        match feed(&output, &input.exp_items, input.exp.saturating_add(output.reclaimed_exp)) {
+            Ok((feeds, leftover)) => break (trial, output, feeds, leftover),
+            // Shrinking by at least an eighth each time keeps the number of
+            // re-solves small; at `input.exp` the loose pool covers everything.
+            Err(shortfall) => allowance += shortfall.max(allowance / 8).max(5),
+        }
+    };
+
+    let (trial, mut output, mut feeds) = match claim_overflow(&trial, &output, &feeds) {
+        Some(pinned) => {
+            let pinned_output = solve(pinned.clone());
+            if reaches_pins(&pinned, &pinned_output) {
+                for ((h, g), feed) in &mut feeds {
+                    let extra = pinned_output.results[*h].gear[*g].spent.exp - output.results[*h].gear[*g].spent.exp;
+                    feed.wasted_exp -= extra;
+                }
+                (pinned, pinned_output, feeds)
+            } else {
+                (trial, output, feeds)
+            }
+        }
+        None => (trial, output, feeds),
+    };
+
+    let wasted_exp: i32 = feeds.iter().map(|(_, feed)| feed.wasted_exp).sum();
+    // The plan was made with `trial.exp`; the rest of the materials are still
+    // there, minus what was lost to overflow.
+    let unplanned = full - trial.exp - wasted_exp;
+    output.leftover.exp += unplanned;
+    for step in &mut output.plan {
+        step.balance.exp += unplanned;
+    }
+    for ((h, g), feed) in &mut feeds {
+        feed.hero_name = output.results[*h].hero_name.clone();
+        feed.gear_type = output.results[*h].gear[*g].gear_type.clone();
+    }
+    output.exp_items = Some(ExpItemPlan {
+        feeds: feeds.into_iter().map(|(_, feed)| feed).collect(),
+        leftover,
+        wasted_exp,
+    });
+    output
+}
+
+/// Covers the EXP each piece of `output` needs with materials, largest
+/// first, topping up from `loose` or with the smallest material that still
+/// fits. Returns the feeds and the materials left, or the EXP that could not
+/// be covered.
+fn feed(output: &OptimizationOutput, stacks: &[ExpItemStack], mut loose: i32) -> Result<(Feeds, Vec<ExpItemStack>), i32> {
+    let mut needs: Vec<(usize, usize, i32)> = output
+        .results
+        .iter()
+        .enumerate()
+        .flat_map(|(h, result)| result.gear.iter().enumerate().map(move |(g, gear)| (h, g, gear.spent.exp)))
+        .filter(|&(_, _, need)| need > 0)
+        .collect();
+    needs.sort_by_key(|&(_, _, need)| std::cmp::Reverse(need));
+    let mut stock: Vec<ExpItemStack> = stacks.iter().copied().filter(|s| s.count > 0).collect();
+    stock.sort_by_key(|s| std::cmp::Reverse(s.exp));
+
+    let mut feeds = Vec::new();
+    let mut shortfall = 0;
+    for (hero, piece, need) in needs {
+        let mut used = vec![0; stock.len()];
+        let mut remaining = need;
+        for (stack, used) in stock.iter_mut().zip(&mut used) {
+            let n = stack.count.min(remaining / stack.exp);
+            stack.count -= n;
+            *used += n;
+            remaining -= n * stack.exp;
+        }
+        let mut loose_exp = 0;
+        let mut wasted_exp = 0;
+        if remaining > 0 {
+            if loose >= remaining {
+                loose_exp = remaining;
+            } else if let Some(i) = stock.iter().rposition(|s| s.count > 0) {
+                // Every material still in stock is larger than what is left.
+                stock[i].count -= 1;
+                used[i] += 1;
+                wasted_exp = stock[i].exp - remaining;
+            } else {
+                loose_exp = loose;
+                shortfall += remaining - loose;
+            }
+            loose -= loose_exp;
+        }
+        let items = stock
+            .iter()
+            .zip(&used)
+            .filter(|(_, &n)| n > 0)
+            .map(|(s, &n)| ExpItemStack { exp: s.exp, count: n })
+            .collect();
+        feeds.push((
+            (hero, piece),
+            ExpFeed { hero_name: String::new(), gear_type: String::new(), items, loose_exp, wasted_exp },
+        ));
+    }
+
+    if shortfall > 0 {
+        return Err(shortfall);
+    }
+    feeds.sort_by_key(|&(key, _)| key);
+    Ok((feeds, stock.into_iter().filter(|s| s.count > 0).collect()))
+}
+
+/// Pins every piece of `trial` to its result in `output`, raising the ones
+/// with overflow as far as the overflow pays for without crossing a gate,
+/// a mastery requirement or a cap. Returns `None` when no piece moves.
+fn claim_overflow(trial: &InputData, output: &OptimizationOutput, feeds: &Feeds) -> Option<InputData> {
+    let game_data = trial.game_data();
+    let mut pinned = trial.clone();
+    let mut extra_exp = 0;
+    for (h, (hero, result)) in pinned.heroes.iter_mut().zip(&output.results).enumerate() {
+        for (g, (gear, piece)) in game_data.pieces_mut(&mut hero.gear).into_iter().zip(&result.gear).enumerate() {
+            let mut level = piece.recommended_enhancement;
+            if let Some((_, feed)) = feeds.iter().find(|(key, _)| *key == (h, g)) {
+                let game_data = game_data.tables_for(gear);
+                let start = if piece.reset { 0 } else { piece.current_enhancement };
+                let fed = piece.spent.exp + feed.wasted_exp;
+                let mut cap = gear.max_enhancement.unwrap_or(game_data.max_enhancement).min(game_data.max_enhancement);
+                if piece.reset {
+                    // At its old level the reset would no longer pay off.
+                    cap = cap.min(piece.current_enhancement - 1);
+                }
+                while level < cap
+                    && game_data.gate(level + 1).is_none()
+                    && game_data.required_mastery(level + 1) <= piece.recommended_mastery
+                    && game_data.exp_cost(level + 1) - game_data.exp_cost(start) <= fed
+                {
+                    level += 1;
+                }
+                extra_exp += game_data.exp_cost(level) - game_data.exp_cost(piece.recommended_enhancement);
+            }
+            gear.min_enhancement = Some(level);
+            gear.max_enhancement = Some(level);
+            gear.target_mastery = Some(piece.recommended_mastery);
+            gear.no_reset = gear.no_reset || !piece.reset;
+        }
+    }
+    if extra_exp == 0 {
+        return None;
+    }
+    pinned.exp = pinned.exp.saturating_add(extra_exp);
+    Some(pinned)
+}
+
+fn reaches_pins(pinned: &InputData, output: &OptimizationOutput) -> bool {
+    let game_data = pinned.game_data();
+    pinned.heroes.iter().zip(&output.results).all(|(hero, result)| {
+        game_data.pieces(&hero.gear).into_iter().zip(&result.gear).all(|(gear, piece)| {
+            Some(piece.recommended_enhancement) == gear.min_enhancement
+                && Some(piece.recommended_mastery) == gear.target_mastery
+        })
+    })
+}
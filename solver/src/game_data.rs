@@ -0,0 +1,527 @@
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use crate::error::SolverError;
+use crate::types::{Gear, HeroGear, Resources, Stats};
+
+/// Tables shipped with the solver, used when the input carries none.
+const BUILTIN_GAME_DATA: &str = include_str!("../data/game_data.json");
+
+/// A level that costs mythics or mythril and needs a minimum mastery. The
+/// mastery requirement holds for every level from this gate up to the next.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Gate {
+    pub level: i32,
+    pub mythics: i32,
+    pub mythril: i32,
+    #[serde(rename = "requiredMastery")]
+    pub required_mastery: i32,
+}
+
+/// Stat gained per enhancement level for all levels up to `up_to`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StatSegment {
+    #[serde(rename = "upTo")]
+    pub up_to: i32,
+    #[serde(rename = "perLevel")]
+    pub per_level: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StatCurve {
+    pub base: f64,
+    /// Multiplier added per mastery level.
+    #[serde(rename = "masteryBonus")]
+    pub mastery_bonus: f64,
+    pub segments: Vec<StatSegment>,
+}
+
+/// The stat a gear slot gives.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SlotStat {
+    Lethality,
+    Health,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SlotDefinition {
+    /// The key of the piece in a hero's `gear`.
+    pub name: String,
+    pub stat: SlotStat,
+}
+
+/// Flat stats a hero gets once `pieces` of their gear, or every slot, reach
+/// `enhancement` and `mastery`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SetBonus {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pieces: Option<usize>,
+    #[serde(default)]
+    pub enhancement: i32,
+    #[serde(default)]
+    pub mastery: i32,
+    pub bonus: Stats,
+}
+
+/// The gear slots every hero has, in the order pieces are reported, and the
+/// bonuses for upgrading them together.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GearSet {
+    pub slots: Vec<SlotDefinition>,
+    #[serde(default)]
+    pub bonuses: Vec<SetBonus>,
+}
+
+impl Default for GearSet {
+    fn default() -> Self {
+        let slot = |name: &str, stat| SlotDefinition { name: name.to_string(), stat };
+        GearSet {
+            slots: vec![
+                slot("helmet", SlotStat::Lethality),
+                slot("gloves", SlotStat::Health),
+                slot("breastplate", SlotStat::Health),
+                slot("boots", SlotStat::Lethality),
+            ],
+            bonuses: Vec::new(),
+        }
+    }
+}
+
+/// A piece's tier and levels, which its stat is computed from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PieceLevel {
+    /// Rank of the piece's tier, see `GameData::tier_rank`.
+    pub tier: usize,
+    pub enhancement: i32,
+    pub mastery: i32,
+}
+
+/// Game tables the solvers run on. A game patch only needs a new JSON file
+/// with a new `version`, not a new build.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GameData {
+    pub version: String,
+    #[serde(rename = "maxEnhancement")]
+    pub max_enhancement: i32,
+    #[serde(rename = "maxMastery")]
+    pub max_mastery: i32,
+    /// Total EXP to reach each enhancement level from 0.
+    #[serde(rename = "expCosts")]
+    pub exp_costs: Vec<i32>,
+    /// Hammers to reach mastery `m`, at index `m - 1`.
+    #[serde(rename = "hammerCosts")]
+    pub hammer_costs: Vec<i32>,
+    /// Mythics to reach mastery `m`, at index `m - 1`.
+    #[serde(rename = "masteryMythicCosts")]
+    pub mastery_mythic_costs: Vec<i32>,
+    pub gates: Vec<Gate>,
+    pub stat: StatCurve,
+    /// Helmet and boots for lethality, gloves and breastplate for health,
+    /// unless given.
+    #[serde(rename = "gearSet", default)]
+    pub gear_set: GearSet,
+    /// Name of the gear tier these tables are for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<String>,
+    /// What crafting a new piece of this tier costs, when lower tiers can be
+    /// crafted into it.
+    #[serde(rename = "craftCost", default, skip_serializing_if = "Option::is_none")]
+    pub craft_cost: Option<Resources>,
+    /// Tables of the lower tiers, lowest first, each in this same format.
+    /// The tables above are the highest tier, used by pieces without a
+    /// `tier`. The gear set always comes from the top level.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiers: Vec<GameData>,
+}
+
+impl GameData {
+    pub fn builtin() -> &'static GameData {
+        static BUILTIN: OnceLock<GameData> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            let data: GameData = serde_json::from_str(BUILTIN_GAME_DATA).expect("built-in game data is valid JSON");
+            data.validate().expect("built-in game data is consistent");
+            data
+        })
+    }
+
+    /// Checks that the tables agree with each other: lengths match the caps,
+    /// costs never decrease, and gates are ordered and within the caps. Error
+    /// fields are relative to the game data object.
+    pub fn validate(&self) -> Result<(), SolverError> {
+        if self.version.trim().is_empty() {
+            return Err(SolverError::validation("version", "must not be empty"));
+        }
+        if self.max_enhancement < 1 {
+            return Err(SolverError::validation("maxEnhancement", "must be positive"));
+        }
+        if self.max_mastery < 1 {
+            return Err(SolverError::validation("maxMastery", "must be positive"));
+        }
+        if self.exp_costs.len() != self.max_enhancement as usize + 1 {
+            return Err(SolverError::validation(
+                "expCosts",
+                format!(
+                    "has {} entries but maxEnhancement {} needs {}",
+                    self.exp_costs.len(),
+                    self.max_enhancement,
+                    self.max_enhancement + 1
+                ),
+            ));
+        }
+        if self.exp_costs[0] != 0 {
+            return Err(SolverError::validation("expCosts[0]", "must be 0"));
+        }
+        check_non_decreasing("expCosts", &self.exp_costs)?;
+
+        for (name, table) in [
+            ("hammerCosts", &self.hammer_costs),
+            ("masteryMythicCosts", &self.mastery_mythic_costs),
+        ] {
+            if table.len() != self.max_mastery as usize {
+                return Err(SolverError::validation(
+                    name,
+                    format!("has {} entries but maxMastery is {}", table.len(), self.max_mastery),
+                ));
+            }
+            if let Some(i) = table.iter().position(|&c| c < 0) {
+                return Err(SolverError::validation(format!("{}[{}]", name, i), "must not be negative"));
+            }
+            check_non_decreasing(name, table)?;
+        }
+
+        let mut previous: Option<&Gate> = None;
+        for (i, gate) in self.gates.iter().enumerate() {
+            let field = |name: &str| format!("gates[{}].{}", i, name);
+            if gate.level < 1 || gate.level > self.max_enhancement {
+                return Err(SolverError::validation(
+                    field("level"),
+                    format!("{} is outside 1..={}", gate.level, self.max_enhancement),
+                ));
+            }
+            if gate.mythics < 0 {
+                return Err(SolverError::validation(field("mythics"), "must not be negative"));
+            }
+            if gate.mythril < 0 {
+                return Err(SolverError::validation(field("mythril"), "must not be negative"));
+            }
+            if gate.required_mastery < 0 || gate.required_mastery > self.max_mastery {
+                return Err(SolverError::validation(
+                    field("requiredMastery"),
+                    format!("{} is outside 0..={}", gate.required_mastery, self.max_mastery),
+                ));
+            }
+            if let Some(prev) = previous {
+                if gate.level <= prev.level {
+                    return Err(SolverError::validation(
+                        field("level"),
+                        format!("{} is not after level {}", gate.level, prev.level),
+                    ));
+                }
+                if gate.required_mastery < prev.required_mastery {
+                    return Err(SolverError::validation(
+                        field("requiredMastery"),
+                        "must not be lower than the previous gate's",
+                    ));
+                }
+            }
+            previous = Some(gate);
+        }
+
+        // Taking one piece all the way up must cost amounts that fit.
+        for (name, amount) in [
+            ("hammerCosts", total(self.hammer_costs.iter().copied())),
+            ("masteryMythicCosts", total(self.mastery_mythic_costs.iter().copied().chain(self.gates.iter().map(|g| g.mythics)))),
+            ("gates", total(self.gates.iter().map(|g| g.mythril))),
+        ] {
+            if amount > i64::from(i32::MAX) {
+                return Err(SolverError::validation(
+                    name,
+                    format!("a piece at the caps costs {} in total, more than {}", amount, i32::MAX),
+                ));
+            }
+        }
+
+        let mut previous_up_to = 0;
+        for (i, segment) in self.stat.segments.iter().enumerate() {
+            if segment.up_to <= previous_up_to {
+                return Err(SolverError::validation(
+                    format!("stat.segments[{}].upTo", i),
+                    format!("{} is not after {}", segment.up_to, previous_up_to),
+                ));
+            }
+            if !segment.per_level.is_finite() || segment.per_level < 0.0 {
+                return Err(SolverError::validation(
+                    format!("stat.segments[{}].perLevel", i),
+                    "must be a non-negative number",
+                ));
+            }
+            previous_up_to = segment.up_to;
+        }
+        if previous_up_to != self.max_enhancement {
+            return Err(SolverError::validation(
+                "stat.segments",
+                format!("end at {} but maxEnhancement is {}", previous_up_to, self.max_enhancement),
+            ));
+        }
+        if !self.stat.base.is_finite() {
+            return Err(SolverError::validation("stat.base", "must be finite"));
+        }
+        if !self.stat.mastery_bonus.is_finite() {
+            return Err(SolverError::validation("stat.masteryBonus", "must be finite"));
+        }
+
+        let slots = &self.gear_set.slots;
+        if slots.is_empty() {
+            return Err(SolverError::validation("gearSet.slots", "must not be empty"));
+        }
+        for (i, slot) in slots.iter().enumerate() {
+            if slot.name.trim().is_empty() {
+                return Err(SolverError::validation(format!("gearSet.slots[{}].name", i), "must not be empty"));
+            }
+            if slots[..i].iter().any(|other| other.name == slot.name) {
+                return Err(SolverError::validation(
+                    format!("gearSet.slots[{}].name", i),
+                    format!("`{}` is used twice", slot.name),
+                ));
+            }
+        }
+        for (i, bonus) in self.gear_set.bonuses.iter().enumerate() {
+            self.check_bonus(bonus, &format!("gearSet.bonuses[{}]", i))?;
+        }
+
+        if let Some(cost) = &self.craft_cost {
+            if !cost.is_non_negative() {
+                return Err(SolverError::validation("craftCost", "must not be negative"));
+            }
+            if self.tier.is_none() {
+                return Err(SolverError::validation("tier", "must name the tier crafted for craftCost"));
+            }
+        }
+        for (i, tier) in self.tiers.iter().enumerate() {
+            let field = format!("tiers[{}]", i);
+            tier.validate().map_err(|e| e.within(&field))?;
+            let Some(name) = tier.tier.as_deref().filter(|name| !name.trim().is_empty()) else {
+                return Err(SolverError::validation(format!("{}.tier", field), "must name the tier"));
+            };
+            if self.tier.as_deref() == Some(name) || self.tiers[..i].iter().any(|other| other.tier.as_deref() == Some(name)) {
+                return Err(SolverError::validation(format!("{}.tier", field), format!("`{}` is used twice", name)));
+            }
+            if !tier.tiers.is_empty() {
+                return Err(SolverError::validation(format!("{}.tiers", field), "must be empty"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks a set bonus against the slots and caps, reporting problems
+    /// under `field`.
+    pub fn check_bonus(&self, bonus: &SetBonus, field: &str) -> Result<(), SolverError> {
+        let field = |name: &str| format!("{}.{}", field, name);
+        let slots = self.slot_count();
+        if bonus.pieces.is_some_and(|pieces| pieces < 1 || pieces > slots) {
+            return Err(SolverError::validation(field("pieces"), format!("must be within 1..={}", slots)));
+        }
+        if bonus.enhancement < 0 || bonus.enhancement > self.max_enhancement {
+            return Err(SolverError::validation(
+                field("enhancement"),
+                format!("{} is outside 0..={}", bonus.enhancement, self.max_enhancement),
+            ));
+        }
+        if bonus.mastery < 0 || bonus.mastery > self.max_mastery {
+            return Err(SolverError::validation(
+                field("mastery"),
+                format!("{} is outside 0..={}", bonus.mastery, self.max_mastery),
+            ));
+        }
+        let stats = [bonus.bonus.lethality, bonus.bonus.health];
+        if stats.iter().any(|stat| !stat.is_finite() || *stat < 0.0) {
+            return Err(SolverError::validation(field("bonus"), "stats must be non-negative numbers"));
+        }
+        Ok(())
+    }
+
+    pub fn exp_cost(&self, enhancement: i32) -> i32 {
+        if enhancement < 0 || enhancement >= self.exp_costs.len() as i32 {
+            return 0;
+        }
+        self.exp_costs[enhancement as usize]
+    }
+
+    pub fn hammer_cost(&self, target_level: i32) -> i32 {
+        if target_level <= 0 || target_level > self.max_mastery {
+            return 0;
+        }
+        self.hammer_costs[target_level as usize - 1]
+    }
+
+    pub fn mastery_mythic_cost(&self, target_mastery: i32) -> i32 {
+        if target_mastery <= 0 || target_mastery > self.max_mastery {
+            return 0;
+        }
+        self.mastery_mythic_costs[target_mastery as usize - 1]
+    }
+
+    pub fn gate(&self, level: i32) -> Option<&Gate> {
+        self.gates.iter().find(|gate| gate.level == level)
+    }
+
+    pub fn mythril_cost(&self, target_level: i32) -> i32 {
+        self.gate(target_level).map_or(0, |gate| gate.mythril)
+    }
+
+    pub fn mythic_cost(&self, target_level: i32) -> i32 {
+        self.gate(target_level).map_or(0, |gate| gate.mythics)
+    }
+
+    pub fn required_mastery(&self, target_level: i32) -> i32 {
+        self.gates
+            .iter()
+            .take_while(|gate| gate.level <= target_level)
+            .last()
+            .map_or(0, |gate| gate.required_mastery)
+    }
+
+    pub fn stat(&self, enh: i32, mastery: i32) -> f64 {
+        let mut base = self.stat.base;
+        let mut from = 0;
+        for segment in &self.stat.segments {
+            let levels = enh.min(segment.up_to) - from;
+            if levels <= 0 {
+                break;
+            }
+            base += levels as f64 * segment.per_level;
+            from = segment.up_to;
+        }
+        base * (1.0 + mastery as f64 * self.stat.mastery_bonus)
+    }
+
+    /// The rank of a tier, from 0 for the lowest listed in `tiers` up to
+    /// `top_tier` for these tables. `None` is the top tier, as is the top
+    /// tier's own name; unknown names give `None`.
+    pub fn tier_rank(&self, name: Option<&str>) -> Option<usize> {
+        match name {
+            None => Some(self.top_tier()),
+            Some(name) if self.tier.as_deref() == Some(name) => Some(self.top_tier()),
+            Some(name) => self.tiers.iter().position(|tier| tier.tier.as_deref() == Some(name)),
+        }
+    }
+
+    pub fn top_tier(&self) -> usize {
+        self.tiers.len()
+    }
+
+    /// The tables of the tier of rank `tier`.
+    pub fn tables(&self, tier: usize) -> &GameData {
+        self.tiers.get(tier).unwrap_or(self)
+    }
+
+    /// The tables `gear` is upgraded with. Tier names are checked by
+    /// validation, so an unknown one falls back to the top tier.
+    pub fn tables_for(&self, gear: &Gear) -> &GameData {
+        self.tables(self.tier_rank(gear.tier.as_deref()).unwrap_or(self.top_tier()))
+    }
+
+    pub fn slot_count(&self) -> usize {
+        self.gear_set.slots.len()
+    }
+
+    pub fn slot_index(&self, name: &str) -> Option<usize> {
+        self.gear_set.slots.iter().position(|slot| slot.name == name)
+    }
+
+    /// A hero's pieces in slot order; slots the gear lacks are empty pieces.
+    pub fn pieces<'a>(&self, gear: &'a HeroGear) -> Vec<&'a Gear> {
+        const EMPTY: &Gear = &Gear {
+            mastery: 0,
+            enhancement: 0,
+            locked: false,
+            no_reset: false,
+            min_enhancement: None,
+            max_enhancement: None,
+            target_mastery: None,
+            tier: None,
+        };
+        self.gear_set.slots.iter().map(|slot| gear.0.get(&slot.name).unwrap_or(EMPTY)).collect()
+    }
+
+    /// Like `pieces`, adding the slots the gear lacks to it.
+    pub fn pieces_mut<'a>(&self, gear: &'a mut HeroGear) -> Vec<&'a mut Gear> {
+        for slot in &self.gear_set.slots {
+            gear.0.entry(slot.name.clone()).or_default();
+        }
+        let mut by_name: std::collections::BTreeMap<&str, &mut Gear> =
+            gear.0.iter_mut().map(|(name, piece)| (name.as_str(), piece)).collect();
+        self.gear_set.slots.iter().map(|slot| by_name.remove(slot.name.as_str()).expect("every slot was added")).collect()
+    }
+
+    /// Stats of pieces at `levels`, in slot order, including the set bonuses
+    /// they earn of the gear set's and the hero's own `set_bonuses`.
+    pub fn stats_at(&self, levels: impl IntoIterator<Item = PieceLevel>, set_bonuses: &[SetBonus]) -> Stats {
+        let mut stats = Stats { lethality: 0.0, health: 0.0 };
+        let levels: Vec<PieceLevel> = levels.into_iter().collect();
+        for (slot, level) in self.gear_set.slots.iter().zip(&levels) {
+            let stat = self.tables(level.tier).stat(level.enhancement, level.mastery);
+            match slot.stat {
+                SlotStat::Lethality => stats.lethality += stat,
+                SlotStat::Health => stats.health += stat,
+            }
+        }
+        for bonus in self.gear_set.bonuses.iter().chain(set_bonuses) {
+            if self.earns(bonus, &levels) {
+                stats.lethality += bonus.bonus.lethality;
+                stats.health += bonus.bonus.health;
+            }
+        }
+        stats
+    }
+
+    /// Whether pieces at `levels` earn `bonus`.
+    pub fn earns(&self, bonus: &SetBonus, levels: &[PieceLevel]) -> bool {
+        let reached = levels
+            .iter()
+            .filter(|level| level.enhancement >= bonus.enhancement && level.mastery >= bonus.mastery)
+            .count();
+        reached >= bonus.pieces.unwrap_or(self.slot_count())
+    }
+
+    pub fn calculate_stats(&self, gear: &HeroGear, set_bonuses: &[SetBonus]) -> Stats {
+        self.stats_at(self.levels(gear), set_bonuses)
+    }
+
+    /// Names of the bonuses `gear` earns, of the gear set's and `set_bonuses`.
+    pub fn earned_bonuses(&self, gear: &HeroGear, set_bonuses: &[SetBonus]) -> Vec<String> {
+        let levels = self.levels(gear);
+        self.gear_set
+            .bonuses
+            .iter()
+            .chain(set_bonuses)
+            .filter(|bonus| self.earns(bonus, &levels))
+            .map(|bonus| bonus.name.clone())
+            .collect()
+    }
+
+    fn levels(&self, gear: &HeroGear) -> Vec<PieceLevel> {
+        self.pieces(gear)
+            .into_iter()
+            .map(|piece| PieceLevel {
+                tier: self.tier_rank(piece.tier.as_deref()).unwrap_or(self.top_tier()),
+                enhancement: piece.enhancement,
+                mastery: piece.mastery,
+            })
+            .collect()
+    }
+}
+
+fn total(costs: impl Iterator<Item = i32>) -> i64 {
+    costs.map(i64::from).sum()
+}
+
+fn check_non_decreasing(name: &str, table: &[i32]) -> Result<(), SolverError> {
+    match table.windows(2).position(|w| w[1] < w[0]) {
+        Some(i) => Err(SolverError::validation(format!("{}[{}]", name, i + 1), "must not be lower than the entry before it")),
+        None => Ok(()),
+    }
+}
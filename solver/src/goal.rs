@@ -0,0 +1,278 @@
+use crate::error::SolverError;
+use crate::greedy::raise_to_floors;
+use crate::model::*;
+use crate::plan::*;
+use crate::types::*;
+
+/// Finds the resources needed to reach `goal` from the current gear, and the
+/// upgrade path. Piece targets are floors whose cost is fixed. Score targets
+/// are then met one level at a time, taking the upgrade with the best
+/// progress per weighted cost, and finally any level that is not needed to
+/// stay on target is given back.
+pub fn seek(input: &InputData, goal: &Goal) -> Result<OptimizationOutput, SolverError> {
+    let game_data = input.game_data();
+    let slots = game_data.slot_count();
+    let (mut items, _) = prepare_items_with(input, &vec![false; input.heroes.len() * slots]);
+    let start = items.clone();
+
+    let mut targeted = Vec::new();
+    for (i, target) in goal.pieces.iter().enumerate() {
+        let field = format!("goal.pieces[{}]", i);
+        let hero = hero_index(input, &target.hero_name).ok_or_else(|| unknown_hero(&field, &target.hero_name))?;
+        for slot in target_slots(game_data, target) {
+            let item = &mut items[hero * slots + slot];
+            if target.enhancement > item.max_enhancement || target.mastery > item.max_mastery {
+                return Err(SolverError::validation(
+                    field,
+                    format!(
+                        "{} {} is capped at enhancement {} and mastery {}",
+                        target.hero_name, game_data.gear_set.slots[slot].name, item.max_enhancement, item.max_mastery
+                    ),
+                ));
+            }
+            let required = item.tables(game_data).required_mastery(target.enhancement);
+            if target.enhancement > item.current_enhancement && required > item.max_mastery {
+                return Err(SolverError::validation(
+                    field,
+                    format!(
+                        "{} {} needs mastery {} for enhancement {} but is capped at mastery {}",
+                        target.hero_name,
+                        game_data.gear_set.slots[slot].name,
+                        required,
+                        target.enhancement,
+                        item.max_mastery
+                    ),
+                ));
+            }
+            item.min_enhancement = item.min_enhancement.max(target.enhancement);
+            item.min_mastery = item.min_mastery.max(target.mastery);
+            targeted.push((hero * slots + slot, target.enhancement, target.mastery));
+        }
+    }
+
+    let unlimited = Resources { exp: i32::MAX / 2, hammers: i32::MAX / 2, mythics: i32::MAX / 2, mythril: i32::MAX / 2 };
+    let (floor_upgrades, _) = raise_to_floors(game_data, &mut items, unlimited);
+    // The checks above make every piece target reachable; a cost for one
+    // that was not reached would be wrong.
+    if let Some(&(i, enhancement, mastery)) = targeted
+        .iter()
+        .find(|&&(i, enhancement, mastery)| items[i].current_enhancement < enhancement || items[i].mastery < mastery)
+    {
+        return Err(SolverError::internal(format!(
+            "piece target {}/{} stopped at {}/{}",
+            enhancement, mastery, items[i].current_enhancement, items[i].mastery
+        )));
+    }
+    let floors = items.clone();
+
+    let mut heroes = Vec::with_capacity(goal.scores.len());
+    for (i, target) in goal.scores.iter().enumerate() {
+        let hero = match &target.hero_name {
+            Some(name) => {
+                let field = format!("goal.scores[{}].heroName", i);
+                Some(hero_index(input, name).ok_or_else(|| unknown_hero(&field, name))?)
+            }
+            None => None,
+        };
+        heroes.push(hero);
+    }
+    let targets: Vec<(Option<usize>, &ScoreTarget)> = heroes.into_iter().zip(&goal.scores).collect();
+    let weights = goal.cost_weights.unwrap_or_else(|| default_weights(game_data));
+    let weighted = |cost: Resources| {
+        cost.exp as f64 * weights.exp
+            + cost.hammers as f64 * weights.hammers
+            + cost.mythics as f64 * weights.mythics
+            + cost.mythril as f64 * weights.mythril
+    };
+
+    let met = |items: &[OptimizationItem]| {
+        targets.iter().all(|(hero, target)| stat_value(game_data, items, *hero, target.stat) >= target.at_least - 1e-9)
+    };
+
+    while !met(&items) {
+        let values: Vec<f64> = targets
+            .iter()
+            .map(|(hero, target)| stat_value(game_data, &items, *hero, target.stat))
+            .collect();
+        // Progress towards each target, as a share of the target, counting
+        // nothing beyond it.
+        let gain = |i: usize, enhancement: i32, mastery: i32| -> f64 {
+            let first = i / slots * slots;
+            let own = &items[first..first + slots];
+            let moved: Vec<PieceLevel> = (first..first + slots)
+                .map(|j| if j == i { items[j].level(enhancement, mastery) } else { level(&items[j]) })
+                .collect();
+            targets
+                .iter()
+                .zip(&values)
+                .filter(|((hero, _), _)| hero.is_none_or(|hero| i / slots == hero))
+                .map(|((_, target), &value)| {
+                    let delta = hero_stat(game_data, own, target.stat, moved.iter().copied())
+                        - hero_stat(game_data, own, target.stat, own.iter().map(level));
+                    ((value + delta).min(target.at_least) - value.min(target.at_least)) / target.at_least.abs().max(1e-12)
+                })
+                .sum()
+        };
+
+        let mut best: Option<(usize, i32, i32, f64)> = None;
+        for (i, item) in items.iter().enumerate() {
+            let tables = item.tables(game_data);
+            let mut moves = Vec::with_capacity(2);
+            if item.current_enhancement < item.max_enhancement {
+                let next = item.current_enhancement + 1;
+                let mastery = item.mastery.max(tables.required_mastery(next));
+                if mastery <= item.max_mastery {
+                    moves.push((next, mastery));
+                }
+            }
+            if item.mastery < item.max_mastery {
+                moves.push((item.current_enhancement, item.mastery + 1));
+            }
+            for (enhancement, mastery) in moves {
+                let Some(cost) = upgrade_cost(tables, item.current_enhancement, item.mastery, enhancement, mastery) else {
+                    continue;
+                };
+                let gain = gain(i, enhancement, mastery);
+                if gain <= 0.0 {
+                    continue;
+                }
+                let cost = weighted(cost);
+                let rate = if cost > 0.0 { gain / cost } else { f64::INFINITY };
+                if best.is_none_or(|(_, _, _, best_rate)| rate > best_rate) {
+                    best = Some((i, enhancement, mastery, rate));
+                }
+            }
+        }
+        let Some((i, enhancement, mastery, _)) = best else {
+            let (j, (hero, target)) = targets
+                .iter()
+                .enumerate()
+                .find(|(_, (hero, target))| stat_value(game_data, &items, *hero, target.stat) < target.at_least - 1e-9)
+                .expect("an unmet target remains");
+            return Err(SolverError::validation(
+                format!("goal.scores[{}].atLeast", j),
+                format!("cannot be reached; the most possible is {}", stat_value(game_data, &items, *hero, target.stat)),
+            ));
+        };
+        items[i].current_enhancement = enhancement;
+        items[i].mastery = mastery;
+    }
+
+    // Give back levels the targets do not need.
+    loop {
+        let mut lowered = false;
+        for i in (0..items.len()).rev() {
+            for lower_mastery in [false, true] {
+                let item = &items[i];
+                let floor = &floors[i];
+                let (enhancement, mastery) = if lower_mastery {
+                    (item.current_enhancement, item.mastery - 1)
+                } else {
+                    (item.current_enhancement - 1, item.mastery)
+                };
+                if enhancement < floor.current_enhancement
+                    || mastery < floor.mastery
+                    || mastery < item.tables(game_data).required_mastery(enhancement)
+                {
+                    continue;
+                }
+                let mut trial = items.clone();
+                trial[i].current_enhancement = enhancement;
+                trial[i].mastery = mastery;
+                if met(&trial) {
+                    items = trial;
+                    lowered = true;
+                }
+            }
+        }
+        if !lowered {
+            break;
+        }
+    }
+
+    let required = start
+        .iter()
+        .zip(&items)
+        .fold(Resources::default(), |acc, (s, item)| acc + piece_spending(game_data, s, item).0);
+    let budget = input.budget();
+    let shortfall = Resources {
+        exp: (required.exp - budget.exp).max(0),
+        hammers: (required.hammers - budget.hammers).max(0),
+        mythics: (required.mythics - budget.mythics).max(0),
+        mythril: (required.mythril - budget.mythril).max(0),
+    };
+
+    // The plan spends exactly what is required.
+    let mut plan_input = input.clone();
+    plan_input.set_budget(required);
+    let mut upgrades = floor_upgrades;
+    upgrades.extend(upgrades_between(game_data, &floors, &items));
+    let mut output = build_output(&plan_input, &start, &items);
+    output.plan = build_plan(&plan_input, &start, &upgrades);
+    output.goal = Some(GoalResult {
+        required,
+        shortfall,
+        achieved: targets
+            .iter()
+            .map(|(hero, target)| stat_value(game_data, &items, *hero, target.stat))
+            .collect(),
+    });
+    Ok(output)
+}
+
+fn hero_index(input: &InputData, name: &str) -> Option<usize> {
+    input.heroes.iter().position(|hero| hero.name == name)
+}
+
+fn unknown_hero(field: &str, name: &str) -> SolverError {
+    SolverError::validation(field, format!("no hero named `{}`", name))
+}
+
+fn target_slots(game_data: &GameData, target: &PieceTarget) -> Vec<usize> {
+    match &target.gear_type {
+        Some(gear_type) => game_data.slot_index(gear_type).into_iter().collect(),
+        None => (0..game_data.slot_count()).collect(),
+    }
+}
+
+fn level(item: &OptimizationItem) -> PieceLevel {
+    item.level(item.current_enhancement, item.mastery)
+}
+
+/// `stat` summed over the heroes, or just `hero`, set bonuses included.
+fn stat_value(game_data: &GameData, items: &[OptimizationItem], hero: Option<usize>, stat: TargetStat) -> f64 {
+    items
+        .chunks(game_data.slot_count())
+        .enumerate()
+        .filter(|(h, _)| hero.is_none_or(|hero| *h == hero))
+        .map(|(_, own)| hero_stat(game_data, own, stat, own.iter().map(level)))
+        .sum()
+}
+
+/// `stat` of one hero whose pieces are at `levels`.
+fn hero_stat(
+    game_data: &GameData,
+    hero: &[OptimizationItem],
+    stat: TargetStat,
+    levels: impl IntoIterator<Item = PieceLevel>,
+) -> f64 {
+    let stats = game_data.stats_at(levels, &hero[0].set_bonuses);
+    match stat {
+        TargetStat::Score => stats.lethality * hero[0].weights_lethality + stats.health * hero[0].weights_health,
+        TargetStat::Lethality => stats.lethality,
+        TargetStat::Health => stats.health,
+    }
+}
+
+/// One over what it takes to max a single piece from scratch, so that every
+/// resource is measured as a share of a maxed piece.
+fn default_weights(game_data: &GameData) -> CostWeights {
+    let cost = upgrade_cost(game_data, 0, 0, game_data.max_enhancement, game_data.max_mastery).unwrap_or_default();
+    let per = |amount: i32| if amount > 0 { 1.0 / amount as f64 } else { 0.0 };
+    CostWeights {
+        exp: per(cost.exp),
+        hammers: per(cost.hammers),
+        mythics: per(cost.mythics),
+        mythril: per(cost.mythril),
+    }
+}
@@ -1,198 +1,447 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use crate::model::*;
+use crate::objective::Gains;
+use crate::plan::*;
 use crate::types::*;
 
-struct OptimizationItem {
-    // hero_name: String, // Not strictly needed for calculation but good for debugging
-    // gear_type: String,
-    is_lethality: bool,
-    mastery: i32,
-    weights_lethality: f64,
-    weights_health: f64,
-    current_enhancement: i32,
+pub fn solve_greedy(input: InputData) -> OptimizationOutput {
+    let game_data = input.game_data();
+    let (mut all_gear, budget) = prepare_items(&input);
+    let start = all_gear.clone();
+    let (mut upgrades, budget) = raise_to_floors(game_data, &mut all_gear, budget);
+    upgrades.extend(run_greedy(game_data, input.objective, &mut all_gear, budget));
+    let mut output = build_output(&input, &start, &all_gear);
+    output.plan = build_plan(&input, &start, &upgrades);
+    output
 }
 
-pub fn solve_greedy(input: InputData) -> OptimizationOutput {
-    let mut total_exp = input.exp;
+/// Raises every item to its `min_mastery` and `min_enhancement`, buying
+/// mastery prerequisites on the way, before anything is optimized. Stops at
+/// the highest affordable level when the budget runs out. Returns the
+/// upgrades made and the budget left.
+pub fn raise_to_floors(
+    game_data: &GameData,
+    all_gear: &mut [OptimizationItem],
+    budget: Resources,
+) -> (Vec<Upgrade>, Resources) {
+    let mut upgrades = Vec::new();
+    let mut remaining = budget;
+
+    for (i, item) in all_gear.iter_mut().enumerate() {
+        let game_data = item.tables(game_data);
+        while item.mastery < item.min_mastery {
+            let cost = mastery_cost(game_data, item.mastery, item.mastery + 1);
+            if !(remaining - cost).is_non_negative() {
+                break;
+            }
+            remaining -= cost;
+            upgrades.push(Upgrade { item: i, kind: UpgradeKind::Mastery, from: item.mastery, to: item.mastery + 1 });
+            item.mastery += 1;
+        }
+
+        while item.current_enhancement < item.min_enhancement {
+            let next_lvl = item.current_enhancement + 1;
+            let mastery = item.mastery.max(game_data.required_mastery(next_lvl));
+            if mastery > item.max_mastery {
+                break;
+            }
+            let cost = mastery_cost(game_data, item.mastery, mastery) + enhancement_cost(game_data, item.current_enhancement, next_lvl);
+            if !(remaining - cost).is_non_negative() {
+                break;
+            }
+            remaining -= cost;
+            if mastery > item.mastery {
+                upgrades.push(Upgrade { item: i, kind: UpgradeKind::Mastery, from: item.mastery, to: mastery });
+                item.mastery = mastery;
+            }
+            upgrades.push(Upgrade {
+                item: i,
+                kind: UpgradeKind::Enhance,
+                from: item.current_enhancement,
+                to: next_lvl,
+            });
+            item.current_enhancement = next_lvl;
+        }
+    }
+
+    (upgrades, remaining)
+}
+
+/// Spends `budget` on `all_gear` one level at a time, always taking the
+/// upgrade with the best `objective` gain per resource spent. A piece may
+/// also jump straight to a set bonus milestone, since the bonus only shows in
+/// the gain of the step that reaches it. Returns the upgrades in the order
+/// they were made.
+///
+/// Each round takes the best EXP upgrade and then the best hammer upgrade,
+/// both scored before either is made. Candidates wait in one heap per
+/// resource and only the pieces whose gains an upgrade changed are scored
+/// again; since the budget only shrinks, a candidate it cannot pay for is
+/// dropped for good.
+pub fn run_greedy(
+    game_data: &GameData,
+    objective: Objective,
+    all_gear: &mut [OptimizationItem],
+    budget: Resources,
+) -> Vec<Upgrade> {
+    let mut upgrades = Vec::new();
     // We do NOT pool hammers. We only use the available hammers.
-    let mut remaining_hammers = input.hammers;
-    let mut remaining_mythril = input.mythril;
-    let mut remaining_mythics = input.mythics;
-
-    let mut all_gear = Vec::new();
-    
-    // Helper to process a single gear piece
-    let mut process_gear = |gear: &Gear, is_lethality: bool, weights_lethality: f64, weights_health: f64| {
-        let (start_enhancement, cost_to_reclaim) = if gear.enhancement >= 101 {
-            (gear.enhancement, 0)
-        } else {
-            (0, exp_cost(gear.enhancement))
-        };
-        
-        total_exp += cost_to_reclaim;
-        
-        all_gear.push(OptimizationItem {
-            is_lethality,
-            mastery: gear.mastery,
-            weights_lethality,
-            weights_health,
-            current_enhancement: start_enhancement,
-        });
-    };
-
-    for hero in &input.heroes {
-        process_gear(&hero.gear.helmet, true, hero.weights.lethality, hero.weights.health);
-        process_gear(&hero.gear.gloves, false, hero.weights.lethality, hero.weights.health);
-        process_gear(&hero.gear.breastplate, false, hero.weights.lethality, hero.weights.health);
-        process_gear(&hero.gear.boots, true, hero.weights.lethality, hero.weights.health);
+    let mut remaining = budget;
+    let slots = game_data.slot_count();
+    let mut gains = Gains::new(objective, game_data, all_gear);
+    let mut queue = Queue { exp: BinaryHeap::new(), hammer: BinaryHeap::new(), versions: vec![0; all_gear.len()] };
+    for i in 0..all_gear.len() {
+        queue.score(game_data, &gains, all_gear, i);
+    }
+
+    loop {
+        let best_exp = pop_affordable(&mut queue.exp, &queue.versions, remaining);
+        let best_hammer = pop_affordable(&mut queue.hammer, &queue.versions, remaining);
+        let mut changed = Vec::with_capacity(2);
+
+        if let Some(best) = best_exp {
+            let idx = best.item;
+            if best.mastery > all_gear[idx].mastery {
+                upgrades.push(Upgrade {
+                    item: idx,
+                    kind: UpgradeKind::Mastery,
+                    from: all_gear[idx].mastery,
+                    to: best.mastery,
+                });
+            }
+            upgrades.push(Upgrade {
+                item: idx,
+                kind: UpgradeKind::Enhance,
+                from: all_gear[idx].current_enhancement,
+                to: best.level,
+            });
+            all_gear[idx].current_enhancement = best.level;
+            // The mastery prerequisite is bought together with the level.
+            all_gear[idx].mastery = best.mastery;
+            remaining -= best.cost;
+            changed.push(idx / slots);
+        }
+
+        // The EXP upgrade may have spent the same hammers and mythics or already
+        // raised this mastery; if so the piece is scored again below.
+        let best_hammer = best_hammer
+            .filter(|best| all_gear[best.item].mastery == best.from && (remaining - best.cost).is_non_negative());
+        if let Some(best) = best_hammer {
+            upgrades.push(Upgrade { item: best.item, kind: UpgradeKind::Mastery, from: best.from, to: best.mastery });
+            all_gear[best.item].mastery = best.mastery;
+            remaining -= best.cost;
+            changed.push(best.item / slots);
+        }
+
+        if changed.is_empty() {
+            break;
+        }
+        changed.dedup();
+        for hero in gains.update(all_gear, &changed) {
+            for i in hero * slots..(hero + 1) * slots {
+                queue.score(game_data, &gains, all_gear, i);
+            }
+        }
+    }
+    upgrades
+}
+
+/// An upgrade of one piece waiting in the greedy loop's queue.
+struct Candidate {
+    /// Gain per EXP for enhancements, per hammer for mastery.
+    efficiency: f64,
+    item: usize,
+    /// Position among the piece's targets, which breaks ties after the item.
+    order: usize,
+    /// The piece's version when this was scored.
+    version: u32,
+    /// Mastery the candidate starts from.
+    from: i32,
+    level: i32,
+    mastery: i32,
+    cost: Resources,
+}
+
+impl Candidate {
+    fn key(&self) -> (f64, Reverse<usize>, Reverse<usize>) {
+        (self.efficiency, Reverse(self.item), Reverse(self.order))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Higher efficiency first, then the lower item and target, the order in
+/// which a scan over all pieces would meet them.
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (self.key(), other.key());
+        a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2))
     }
+}
+
+/// The best candidate of `heap` still current and within `remaining`. Stale
+/// and unaffordable ones are dropped on the way.
+fn pop_affordable(heap: &mut BinaryHeap<Candidate>, versions: &[u32], remaining: Resources) -> Option<Candidate> {
+    while let Some(candidate) = heap.pop() {
+        if candidate.version == versions[candidate.item] && (remaining - candidate.cost).is_non_negative() {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+struct Queue {
+    exp: BinaryHeap<Candidate>,
+    hammer: BinaryHeap<Candidate>,
+    versions: Vec<u32>,
+}
 
-    let max_enhancement = (EXP_COSTS.len() - 1) as i32;
-    let max_mastery = 20;
-    let mut remaining_exp = total_exp;
+impl Queue {
+    /// Replaces the candidates of item `i` with ones scored on its current
+    /// state.
+    fn score(&mut self, game_data: &GameData, gains: &Gains, all_gear: &[OptimizationItem], i: usize) {
+        self.versions[i] += 1;
+        let version = self.versions[i];
+        let item = &all_gear[i];
+        let tables = item.tables(game_data);
+        let candidate = |order, efficiency: f64, level, mastery, cost| {
+            // Like the scan this replaces, nothing at or below -1 (or NaN)
+            // is taken, and -0.0 ties with 0.0.
+            (efficiency > -1.0).then_some(Candidate {
+                efficiency: efficiency + 0.0,
+                item: i,
+                order,
+                version,
+                from: item.mastery,
+                level,
+                mastery,
+                cost,
+            })
+        };
 
-    fn calculate_item_score_internal(item: &OptimizationItem, enhancement: i32, mastery: i32) -> f64 {
-        let s = stat(enhancement, mastery);
-        if item.is_lethality {
-            s * item.weights_lethality
-        } else {
-            s * item.weights_health
+        if item.current_enhancement < item.max_enhancement {
+            // The next level, with its mastery prerequisite bought alongside
+            // but not counted in the gain, then any milestones.
+            let next_lvl = item.current_enhancement + 1;
+            let next_mastery = item.mastery.max(tables.required_mastery(next_lvl));
+            let mut targets = vec![(next_lvl, next_mastery, item.mastery)];
+            targets.extend(
+                milestones(game_data, item)
+                    .into_iter()
+                    .filter(|&(level, mastery)| level > item.current_enhancement && (level, mastery) != (next_lvl, next_mastery))
+                    .map(|(level, mastery)| (level, mastery, mastery)),
+            );
+
+            for (order, (level, mastery, scored_mastery)) in targets.into_iter().enumerate() {
+                if level > item.max_enhancement || mastery > item.max_mastery {
+                    continue;
+                }
+                let cost = enhancement_cost(tables, item.current_enhancement, level)
+                    + mastery_cost(tables, item.mastery, mastery);
+                let gain = gains.gain(all_gear, i, level, scored_mastery);
+                let efficiency = if cost.exp == 0 {
+                    f64::INFINITY
+                } else {
+                    gain / (cost.exp as f64)
+                };
+                self.exp.extend(candidate(order, efficiency, level, mastery, cost));
+            }
+        }
+
+        if item.mastery < item.max_mastery {
+            let mut targets = vec![item.mastery + 1];
+            targets.extend(
+                milestones(game_data, item)
+                    .into_iter()
+                    .filter(|&(level, mastery)| level == item.current_enhancement && mastery > item.mastery + 1)
+                    .map(|(_, mastery)| mastery),
+            );
+
+            for (order, next_lvl) in targets.into_iter().enumerate() {
+                if next_lvl > item.max_mastery {
+                    continue;
+                }
+                let cost = mastery_cost(tables, item.mastery, next_lvl);
+                let gain = gains.gain(all_gear, i, item.current_enhancement, next_lvl);
+                let efficiency = if cost.hammers == 0 {
+                    f64::INFINITY
+                } else {
+                    gain / (cost.hammers as f64)
+                };
+                self.hammer.extend(candidate(order, efficiency, item.current_enhancement, next_lvl, cost));
+            }
         }
     }
+}
+
+/// The states at which `item` earns a set bonus milestone it has not reached
+/// yet: at least the bonus's enhancement and mastery, and the mastery that
+/// enhancement requires.
+fn milestones(game_data: &GameData, item: &OptimizationItem) -> Vec<(i32, i32)> {
+    game_data
+        .gear_set
+        .bonuses
+        .iter()
+        .chain(item.set_bonuses.iter())
+        .filter(|bonus| item.current_enhancement < bonus.enhancement || item.mastery < bonus.mastery)
+        .map(|bonus| {
+            let level = item.current_enhancement.max(bonus.enhancement);
+            (level, item.mastery.max(bonus.mastery).max(item.tables(game_data).required_mastery(level)))
+        })
+        .collect()
+}
+
+/// The loop `run_greedy` replaced, which scores every piece again in every
+/// round. Kept as the reference the queue must match exactly.
+#[cfg(test)]
+pub fn run_greedy_rescan(
+    game_data: &GameData,
+    objective: Objective,
+    all_gear: &mut [OptimizationItem],
+    budget: Resources,
+) -> Vec<Upgrade> {
+    let mut upgrades = Vec::new();
+    // We do NOT pool hammers. We only use the available hammers.
+    let mut remaining = budget;
 
     // Greedy Algorithm
     loop {
         let mut did_upgrade = false;
+        let gains = Gains::new(objective, game_data, all_gear);
 
         // 1. Find best EXP upgrade
-        let mut best_exp_idx = None;
+        let mut best_exp: Option<(usize, i32, i32, Resources)> = None;
         let mut best_exp_efficiency = -1.0;
-        let mut best_exp_cost = 0;
-        let mut best_exp_mythril_cost = 0;
-        let mut best_exp_mythic_cost = 0;
 
         for (i, item) in all_gear.iter().enumerate() {
-            if item.current_enhancement >= max_enhancement {
+            if item.current_enhancement >= item.max_enhancement {
                 continue;
             }
+            let tables = item.tables(game_data);
 
+            // The next level, with its mastery prerequisite bought alongside
+            // but not counted in the gain, then any milestones.
             let next_lvl = item.current_enhancement + 1;
-
-            // Mastery Constraint Check
-            let mut mastery_upgrade_hammer_cost = 0;
-            let mut mastery_upgrade_mythic_cost = 0;
-            if next_lvl > 100 {
-                let req_mastery = required_mastery(next_lvl);
-                if item.mastery < req_mastery {
-                    // We need to upgrade mastery. Check if we have enough hammers AND mythics.
-                    for m in (item.mastery + 1)..=req_mastery {
-                        mastery_upgrade_hammer_cost += hammer_cost(m);
-                        mastery_upgrade_mythic_cost += mastery_mythic_cost(m);
-                    }
-                    if mastery_upgrade_hammer_cost > remaining_hammers {
-                        continue;
-                    }
-                    if mastery_upgrade_mythic_cost > remaining_mythics {
-                        continue;
-                    }
+            let next_mastery = item.mastery.max(tables.required_mastery(next_lvl));
+            let mut targets = vec![(next_lvl, next_mastery, item.mastery)];
+            targets.extend(
+                milestones(game_data, item)
+                    .into_iter()
+                    .filter(|&(level, mastery)| level > item.current_enhancement && (level, mastery) != (next_lvl, next_mastery))
+                    .map(|(level, mastery)| (level, mastery, mastery)),
+            );
+
+            for (level, mastery, scored_mastery) in targets {
+                if level > item.max_enhancement || mastery > item.max_mastery {
+                    continue;
+                }
+                let cost = enhancement_cost(tables, item.current_enhancement, level)
+                    + mastery_cost(tables, item.mastery, mastery);
+                if !(remaining - cost).is_non_negative() {
+                    continue;
                 }
-            }
-
-            let cost = exp_cost(next_lvl) - exp_cost(item.current_enhancement);
-            let m_cost = mythril_cost(next_lvl);
-            let mc_cost = mythic_cost(next_lvl);
 
-            if cost > remaining_exp {
-                continue;
-            }
-            
-            if m_cost > remaining_mythril {
-                continue;
-            }
+                let gain = gains.gain(all_gear, i, level, scored_mastery);
 
-            // Total mythic cost = enhancement mythic cost + mastery upgrade mythic cost
-            let total_mythic_cost = mc_cost + mastery_upgrade_mythic_cost;
-            if total_mythic_cost > remaining_mythics {
-                continue;
-            }
+                let efficiency = if cost.exp == 0 {
+                    f64::INFINITY
+                } else {
+                    gain / (cost.exp as f64)
+                };
 
-            let current_score = calculate_item_score_internal(item, item.current_enhancement, item.mastery);
-            let next_score = calculate_item_score_internal(item, next_lvl, item.mastery);
-            let gain = next_score - current_score;
-
-            let efficiency = if cost == 0 {
-                f64::INFINITY
-            } else {
-                gain / (cost as f64)
-            };
-
-            if efficiency > best_exp_efficiency {
-                best_exp_efficiency = efficiency;
-                best_exp_idx = Some(i);
-                best_exp_cost = cost;
-                best_exp_mythril_cost = m_cost;
-                best_exp_mythic_cost = total_mythic_cost;
+                if efficiency > best_exp_efficiency {
+                    best_exp_efficiency = efficiency;
+                    best_exp = Some((i, level, mastery, cost));
+                }
             }
         }
 
         // 2. Find best Hammer upgrade
-        let mut best_hammer_idx = None;
+        let mut best_hammer: Option<(usize, i32, i32, Resources)> = None;
         let mut best_hammer_efficiency = -1.0;
-        let mut best_hammer_cost = 0;
-        let mut best_hammer_mythic_cost = 0;
 
         for (i, item) in all_gear.iter().enumerate() {
-            if item.mastery >= max_mastery {
+            if item.mastery >= item.max_mastery {
                 continue;
             }
 
-            let next_lvl = item.mastery + 1;
-            let cost = hammer_cost(next_lvl);
+            let mut targets = vec![item.mastery + 1];
+            targets.extend(
+                milestones(game_data, item)
+                    .into_iter()
+                    .filter(|&(level, mastery)| level == item.current_enhancement && mastery > item.mastery + 1)
+                    .map(|(_, mastery)| mastery),
+            );
+
+            for next_lvl in targets {
+                if next_lvl > item.max_mastery {
+                    continue;
+                }
+                let cost = mastery_cost(item.tables(game_data), item.mastery, next_lvl);
+                if !(remaining - cost).is_non_negative() {
+                    continue;
+                }
 
-            if cost > remaining_hammers {
-                continue;
-            }
+                let gain = gains.gain(all_gear, i, item.current_enhancement, next_lvl);
 
-            let m_mythic_cost = mastery_mythic_cost(next_lvl);
-            if m_mythic_cost > remaining_mythics {
-                continue;
-            }
+                let efficiency = if cost.hammers == 0 {
+                    f64::INFINITY
+                } else {
+                    gain / (cost.hammers as f64)
+                };
 
-            let current_score = calculate_item_score_internal(item, item.current_enhancement, item.mastery);
-            let next_score = calculate_item_score_internal(item, item.current_enhancement, next_lvl);
-            let gain = next_score - current_score;
-
-            let efficiency = if cost == 0 {
-                f64::INFINITY
-            } else {
-                gain / (cost as f64)
-            };
-            
-            // println!("Hammer Check: i={}, mastery={}, next={}, cost={}, gain={}, eff={}", i, item.mastery, next_lvl, cost, gain, efficiency);
-
-            if efficiency > best_hammer_efficiency {
-                best_hammer_efficiency = efficiency;
-                best_hammer_idx = Some(i);
-                best_hammer_cost = cost;
-                best_hammer_mythic_cost = m_mythic_cost;
+                if efficiency > best_hammer_efficiency {
+                    best_hammer_efficiency = efficiency;
+                    best_hammer = Some((i, item.mastery, next_lvl, cost));
+                }
             }
         }
-        
-        // Debug print
-        // println!("Loop: did_upgrade={}, best_exp_idx={:?}, best_hammer_idx={:?}", did_upgrade, best_exp_idx, best_hammer_idx);
 
         // Apply upgrades
-        if let Some(idx) = best_exp_idx {
-            all_gear[idx].current_enhancement += 1;
-            remaining_exp -= best_exp_cost;
-            remaining_mythril -= best_exp_mythril_cost;
-            remaining_mythics -= best_exp_mythic_cost;
+        if let Some((idx, level, mastery, cost)) = best_exp {
+            if mastery > all_gear[idx].mastery {
+                upgrades.push(Upgrade {
+                    item: idx,
+                    kind: UpgradeKind::Mastery,
+                    from: all_gear[idx].mastery,
+                    to: mastery,
+                });
+            }
+            upgrades.push(Upgrade {
+                item: idx,
+                kind: UpgradeKind::Enhance,
+                from: all_gear[idx].current_enhancement,
+                to: level,
+            });
+            all_gear[idx].current_enhancement = level;
+            // The mastery prerequisite is bought together with the level.
+            all_gear[idx].mastery = mastery;
+            remaining -= cost;
             did_upgrade = true;
         }
 
-        if let Some(idx) = best_hammer_idx {
-            // println!("Upgrading hammer for idx {} to {}", idx, all_gear[idx].mastery + 1);
-            all_gear[idx].mastery += 1;
-            remaining_hammers -= best_hammer_cost;
-            remaining_mythics -= best_hammer_mythic_cost;
+        // The EXP upgrade may have spent the same hammers and mythics or already
+        // raised this mastery; if so the candidate is re-evaluated next round.
+        let best_hammer = best_hammer
+            .filter(|&(idx, from, _, cost)| all_gear[idx].mastery == from && (remaining - cost).is_non_negative());
+        if let Some((idx, from, to, cost)) = best_hammer {
+            upgrades.push(Upgrade { item: idx, kind: UpgradeKind::Mastery, from, to });
+            all_gear[idx].mastery = to;
+            remaining -= cost;
             did_upgrade = true;
         }
 
@@ -200,100 +449,5 @@ pub fn solve_greedy(input: InputData) -> OptimizationOutput {
             break;
         }
     }
-
-    // Reconstruct results
-    let mut new_heroes = input.heroes.clone();
-    let mut solution_index = 0;
-
-    for hero in &mut new_heroes {
-        // Order: helmet, gloves, breastplate, boots
-        if solution_index < all_gear.len() { 
-            hero.gear.helmet.enhancement = all_gear[solution_index].current_enhancement; 
-            hero.gear.helmet.mastery = all_gear[solution_index].mastery;
-        } 
-        solution_index += 1;
-        
-        if solution_index < all_gear.len() { 
-            hero.gear.gloves.enhancement = all_gear[solution_index].current_enhancement; 
-            hero.gear.gloves.mastery = all_gear[solution_index].mastery;
-        } 
-        solution_index += 1;
-        
-        if solution_index < all_gear.len() { 
-            hero.gear.breastplate.enhancement = all_gear[solution_index].current_enhancement; 
-            hero.gear.breastplate.mastery = all_gear[solution_index].mastery;
-        } 
-        solution_index += 1;
-        
-        if solution_index < all_gear.len() { 
-            hero.gear.boots.enhancement = all_gear[solution_index].current_enhancement; 
-            hero.gear.boots.mastery = all_gear[solution_index].mastery;
-        } 
-        solution_index += 1;
-    }
-
-    let mut total_before_score = 0.0;
-    let mut total_after_score = 0.0;
-    let mut results = Vec::new();
-
-    for i in 0..input.heroes.len() {
-        let before_stats = calculate_stats(&input.heroes[i].gear);
-        let before_score = before_stats.lethality * input.heroes[i].weights.lethality
-            + before_stats.health * input.heroes[i].weights.health;
-        total_before_score += before_score;
-
-        let after_stats = calculate_stats(&new_heroes[i].gear);
-        let after_score = after_stats.lethality * new_heroes[i].weights.lethality
-            + after_stats.health * new_heroes[i].weights.health;
-        total_after_score += after_score;
-
-        let mut gear_results = Vec::new();
-        // Helmet
-        gear_results.push(GearResult {
-            gear_type: "helmet".to_string(),
-            current_mastery: input.heroes[i].gear.helmet.mastery,
-            recommended_mastery: new_heroes[i].gear.helmet.mastery,
-            current_enhancement: input.heroes[i].gear.helmet.enhancement,
-            recommended_enhancement: new_heroes[i].gear.helmet.enhancement,
-        });
-        // Gloves
-        gear_results.push(GearResult {
-            gear_type: "gloves".to_string(),
-            current_mastery: input.heroes[i].gear.gloves.mastery,
-            recommended_mastery: new_heroes[i].gear.gloves.mastery,
-            current_enhancement: input.heroes[i].gear.gloves.enhancement,
-            recommended_enhancement: new_heroes[i].gear.gloves.enhancement,
-        });
-        // Breastplate
-        gear_results.push(GearResult {
-            gear_type: "breastplate".to_string(),
-            current_mastery: input.heroes[i].gear.breastplate.mastery,
-            recommended_mastery: new_heroes[i].gear.breastplate.mastery,
-            current_enhancement: input.heroes[i].gear.breastplate.enhancement,
-            recommended_enhancement: new_heroes[i].gear.breastplate.enhancement,
-        });
-        // Boots
-        gear_results.push(GearResult {
-            gear_type: "boots".to_string(),
-            current_mastery: input.heroes[i].gear.boots.mastery,
-            recommended_mastery: new_heroes[i].gear.boots.mastery,
-            current_enhancement: input.heroes[i].gear.boots.enhancement,
-            recommended_enhancement: new_heroes[i].gear.boots.enhancement,
-        });
-
-        results.push(OptimizationResult {
-            hero_name: input.heroes[i].name.clone(),
-            gear: gear_results,
-            before_stats,
-            after_stats,
-            before_score,
-            after_score,
-        });
-    }
-
-    OptimizationOutput {
-        results,
-        total_before_score,
-        total_after_score,
-    }
+    upgrades
 }
@@ -1,70 +1,182 @@
 use wasm_bindgen::prelude::*;
 
 mod types;
+mod error;
+mod game_data;
+mod model;
+mod objective;
+mod plan;
+mod reset;
 mod greedy;
+mod exact;
+mod local_search;
+mod strategy;
+mod shadow;
+mod pareto;
+mod shop;
+mod exp_items;
+mod craft;
+mod batch;
+mod bottleneck;
+mod calculator;
+mod goal;
+mod schedule;
+mod validate;
+mod verify;
 
-use types::*;
-use greedy::solve_greedy;
+pub use error::{ErrorKind, SolveResult, SolverError};
+pub use types::*;
+pub use calculator::cost_between;
+pub use verify::verify_plan;
 
+/// Solves a JSON `InputData` and returns a JSON `SolveResult`: `{"ok": ...}`
+/// with the output, or `{"error": {"kind", "field", "message"}}`.
 #[wasm_bindgen]
 pub fn solve(data: &str) -> String {
-    let input: InputData = match serde_json::from_str(data) {
-        Ok(v) => v,
-        Err(e) => return format!("Error parsing input: {}", e),
-    };
+    to_json(&SolveResult::from(parse_input(data).and_then(solve_input)))
+}
+
+/// Solves a JSON `BatchInput`, one `InputData` per player, and returns a JSON
+/// `SolveResult` with the `BatchOutput`. A rejected player does not fail the
+/// batch: their own `result` holds the error.
+#[wasm_bindgen]
+pub fn solve_batch(data: &str) -> String {
+    to_json(&SolveResult::from(parse_json(data).map(solve_batch_input)))
+}
+
+/// Checks the JSON `PlanProposal` with `verify_plan` and returns a JSON
+/// `SolveResult` with the `PlanCheck`. A plan that breaks rules is still
+/// `ok`; only an input that cannot be read or validated is an `error`.
+#[wasm_bindgen]
+pub fn verify(data: &str) -> String {
+    let check = parse_json::<PlanProposal>(data)
+        .and_then(|proposal| verify_plan(&proposal.input, &proposal.gear).map_err(|e| e.within("input")));
+    to_json(&SolveResult::from(check))
+}
+
+/// Prices the JSON `CostQuery` with `cost_between` and returns a JSON
+/// `SolveResult` with the `TransitionCost`.
+#[wasm_bindgen]
+pub fn cost(data: &str) -> String {
+    let cost = parse_json::<CostQuery>(data).and_then(|query| match &query.game_data {
+        Some(game_data) => {
+            game_data.validate().map_err(|e| e.within("gameData"))?;
+            cost_between(game_data, &query.from, &query.to)
+        }
+        None => cost_between(GameData::builtin(), &query.from, &query.to),
+    });
+    to_json(&SolveResult::from(cost))
+}
 
-    let output = solve_greedy(input);
+fn to_json<T: serde::Serialize>(result: &SolveResult<T>) -> String {
+    serde_json::to_string(result).unwrap_or_else(|e| {
+        let error: SolveResult = SolveResult::Error(SolverError::internal(format!("Error serializing output: {}", e)));
+        serde_json::to_string(&error).expect("errors always serialize")
+    })
+}
+
+pub fn parse_input(data: &str) -> Result<InputData, SolverError> {
+    parse_json(data)
+}
 
-    serde_json::to_string(&output).unwrap_or_else(|e| format!("Error serializing output: {}", e))
+/// Parses JSON into `T`, reporting the path of the value that did not fit.
+pub fn parse_json<T: serde::de::DeserializeOwned>(data: &str) -> Result<T, SolverError> {
+    let mut deserializer = serde_json::Deserializer::from_str(data);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(|e| {
+        let path = e.path().to_string();
+        // `.` is the root and `?` a position serde could not name.
+        let field = Some(path).filter(|path| path != "." && path != "?");
+        SolverError::parse(field, e.into_inner().to_string())
+    })?;
+    deserializer
+        .end()
+        .map_err(|e| SolverError::parse(None, e.to_string()))?;
+    Ok(value)
+}
+
+/// Solves every player of `batch` and sums up the alliance.
+pub fn solve_batch_input(batch: BatchInput) -> BatchOutput {
+    batch::run(batch)
+}
+
+/// Validates `input` and runs the strategy it selects. Validation bounds the
+/// input so that no solver panics on it; where panics unwind (not in Wasm), a
+/// panic is still reported as an internal error.
+pub fn solve_input(mut input: InputData) -> Result<OptimizationOutput, SolverError> {
+    let warnings = validate::validate_input(&mut input)?;
+    let output = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+        match (input.goal.take(), input.schedule.take()) {
+            (Some(goal), _) => goal::seek(&input, &goal),
+            (None, Some(schedule)) => Ok(schedule::plan(input, &schedule)),
+            (None, None) => Ok(strategy::run(input)),
+        }
+    }))
+    .map_err(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "solver panicked".to_string());
+        SolverError::internal(message)
+    })??;
+    Ok(OptimizationOutput { warnings, ..output })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use greedy::solve_greedy;
+    use exact::solve_exact;
+
+    fn unwrap_ok(json: &str) -> OptimizationOutput {
+        let result: SolveResult = serde_json::from_str(json).unwrap();
+        Result::from(result).unwrap()
+    }
 
     #[test]
     fn test_exp_cost() {
-        assert_eq!(exp_cost(0), 0);
-        assert_eq!(exp_cost(1), 10);
-        assert_eq!(exp_cost(200), 575320);
-        assert_eq!(exp_cost(-1), 0);
-        assert_eq!(exp_cost(1000), 0);
+        assert_eq!(GameData::builtin().exp_cost(0), 0);
+        assert_eq!(GameData::builtin().exp_cost(1), 10);
+        assert_eq!(GameData::builtin().exp_cost(200), 575320);
+        assert_eq!(GameData::builtin().exp_cost(-1), 0);
+        assert_eq!(GameData::builtin().exp_cost(1000), 0);
     }
 
     #[test]
     fn test_stat() {
         // Base case: enh 0, mastery 0 -> 0.15
-        assert!((stat(0, 0) - 0.15).abs() < 1e-10);
+        assert!((GameData::builtin().stat(0, 0) - 0.15).abs() < 1e-10);
         
         // Enh 100, mastery 0 -> 0.15 + 100 * 0.0035 = 0.15 + 0.35 = 0.5
-        assert!((stat(100, 0) - 0.5).abs() < 1e-10);
+        assert!((GameData::builtin().stat(100, 0) - 0.5).abs() < 1e-10);
 
         // Enh 101, mastery 0 -> 0.5 + 1 * 0.005 = 0.505
-        assert!((stat(101, 0) - 0.505).abs() < 1e-10);
+        assert!((GameData::builtin().stat(101, 0) - 0.505).abs() < 1e-10);
 
         // Enh 0, mastery 10 -> 0.15 * (1 + 10 * 0.1) = 0.15 * 2 = 0.3
-        assert!((stat(0, 10) - 0.3).abs() < 1e-10);
+        assert!((GameData::builtin().stat(0, 10) - 0.3).abs() < 1e-10);
     }
 
     #[test]
     fn test_hammer_cost() {
-        assert_eq!(hammer_cost(1), 10);
-        assert_eq!(hammer_cost(2), 20);
-        assert_eq!(hammer_cost(20), 200);
-        assert_eq!(hammer_cost(21), 0); // Invalid
+        assert_eq!(GameData::builtin().hammer_cost(1), 10);
+        assert_eq!(GameData::builtin().hammer_cost(2), 20);
+        assert_eq!(GameData::builtin().hammer_cost(20), 200);
+        assert_eq!(GameData::builtin().hammer_cost(21), 0); // Invalid
     }
 
     #[test]
     fn test_solve_greedy_level_101() {
         let hero = HeroWeights {
             name: "TestHero".to_string(),
-            gear: HeroGear {
-                helmet: Gear { mastery: 0, enhancement: 105 }, // Should NOT be reset
-                gloves: Gear { mastery: 0, enhancement: 10 }, // Should be reset
-                breastplate: Gear { mastery: 0, enhancement: 0 },
-                boots: Gear { mastery: 0, enhancement: 0 },
-            },
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 0, enhancement: 105, ..Default::default() }), // Should NOT be reset
+                ("gloves", Gear { mastery: 0, enhancement: 10, ..Default::default() }), // Should be reset
+                ("breastplate", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+                ("boots", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+            ]),
             weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
         };
         
         let input = InputData {
@@ -73,12 +185,13 @@ mod tests {
             hammers: 0,
             mythics: 0,
             mythril: 0,
+            ..Default::default()
         };
 
         let json_input = serde_json::to_string(&input).unwrap();
         let json_output = solve(&json_input);
         
-        let output: OptimizationOutput = serde_json::from_str(&json_output).unwrap();
+        let output: OptimizationOutput = unwrap_ok(&json_output);
         
         assert_eq!(output.results.len(), 1);
         
@@ -93,13 +206,14 @@ mod tests {
     fn test_reset_at_level_100() {
         let hero = HeroWeights {
             name: "TestReset".to_string(),
-            gear: HeroGear {
-                helmet: Gear { mastery: 10, enhancement: 100 }, // Should be reset
-                gloves: Gear { mastery: 0, enhancement: 0 },
-                breastplate: Gear { mastery: 0, enhancement: 0 },
-                boots: Gear { mastery: 0, enhancement: 0 },
-            },
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 10, enhancement: 100, ..Default::default() }), // Should be reset
+                ("gloves", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+                ("breastplate", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+                ("boots", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+            ]),
             weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
         };
         
         // 0 extra exp, but we reclaim exp from helmet (level 100)
@@ -109,11 +223,12 @@ mod tests {
             hammers: 0,
             mythics: 0,
             mythril: 0,
+            ..Default::default()
         };
 
         let json_input = serde_json::to_string(&input).unwrap();
         let json_output = solve(&json_input);
-        let output: OptimizationOutput = serde_json::from_str(&json_output).unwrap();
+        let output: OptimizationOutput = unwrap_ok(&json_output);
         
         let res = &output.results[0];
         
@@ -135,13 +250,14 @@ mod tests {
     fn test_no_reset_at_level_101() {
         let hero = HeroWeights {
             name: "TestNoReset".to_string(),
-            gear: HeroGear {
-                helmet: Gear { mastery: 10, enhancement: 101 }, // Should NOT be reset
-                gloves: Gear { mastery: 0, enhancement: 0 },
-                breastplate: Gear { mastery: 0, enhancement: 0 },
-                boots: Gear { mastery: 0, enhancement: 0 },
-            },
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 10, enhancement: 101, ..Default::default() }), // Should NOT be reset
+                ("gloves", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+                ("breastplate", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+                ("boots", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+            ]),
             weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
         };
         
         // 0 extra exp. Helmet is 101, so no reclaim.
@@ -151,11 +267,12 @@ mod tests {
             hammers: 0,
             mythics: 0,
             mythril: 0,
+            ..Default::default()
         };
 
         let json_input = serde_json::to_string(&input).unwrap();
         let json_output = solve(&json_input);
-        let output: OptimizationOutput = serde_json::from_str(&json_output).unwrap();
+        let output: OptimizationOutput = unwrap_ok(&json_output);
         
         let res = &output.results[0];
         
@@ -175,14 +292,15 @@ mod tests {
     fn test_mastery_gate() {
         let hero = HeroWeights {
             name: "TestGate".to_string(),
-            gear: HeroGear {
-                helmet: Gear { mastery: 9, enhancement: 100 }, // Needs mastery 10 to go to 101
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 9, enhancement: 100, ..Default::default() }), // Needs mastery 10 to go to 101
                 // Set other gear to max so they don't consume resources
-                gloves: Gear { mastery: 20, enhancement: 200 },
-                breastplate: Gear { mastery: 20, enhancement: 200 },
-                boots: Gear { mastery: 20, enhancement: 200 },
-            },
+                ("gloves", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("breastplate", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("boots", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+            ]),
             weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
         };
         
         // Case 1: Lots of EXP, 0 Hammers. Should stay at 100.
@@ -192,8 +310,9 @@ mod tests {
             hammers: 0,
             mythics: 0,
             mythril: 0,
+            ..Default::default()
         };
-        let output1: OptimizationOutput = serde_json::from_str(&solve(&serde_json::to_string(&input1).unwrap())).unwrap();
+        let output1: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input1).unwrap()));
         assert_eq!(output1.results[0].gear[0].recommended_enhancement, 100);
         
         // Case 2: Lots of EXP, Lots of Hammers. Should upgrade mastery to 10, then enhancement to 101+.
@@ -203,8 +322,9 @@ mod tests {
             hammers: 1000,
             mythics: 100, // Added mythics to allow upgrade past 100
             mythril: 0,
+            ..Default::default()
         };
-        let output2: OptimizationOutput = serde_json::from_str(&solve(&serde_json::to_string(&input2).unwrap())).unwrap();
+        let output2: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input2).unwrap()));
         assert!(output2.results[0].gear[0].recommended_enhancement > 100);
         assert!(output2.results[0].gear[0].recommended_mastery >= 10);
     }
@@ -213,13 +333,14 @@ mod tests {
     fn test_solve_greedy() {
         let hero = HeroWeights {
             name: "TestHero".to_string(),
-            gear: HeroGear {
-                helmet: Gear { mastery: 0, enhancement: 0 },
-                gloves: Gear { mastery: 0, enhancement: 0 },
-                breastplate: Gear { mastery: 0, enhancement: 0 },
-                boots: Gear { mastery: 0, enhancement: 0 },
-            },
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+                ("gloves", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+                ("breastplate", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+                ("boots", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+            ]),
             weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
         };
         
         let input = InputData {
@@ -228,12 +349,13 @@ mod tests {
             hammers: 100, // Enough for some mastery upgrades
             mythics: 0,
             mythril: 0,
+            ..Default::default()
         };
 
         let json_input = serde_json::to_string(&input).unwrap();
         let json_output = solve(&json_input);
         
-        let output: OptimizationOutput = serde_json::from_str(&json_output).unwrap();
+        let output: OptimizationOutput = unwrap_ok(&json_output);
         
         assert_eq!(output.results.len(), 1);
         assert!(output.total_after_score > output.total_before_score);
@@ -242,10 +364,10 @@ mod tests {
         let mut used_exp = 0;
         let mut used_hammers = 0;
         for res in &output.results[0].gear {
-             used_exp += exp_cost(res.recommended_enhancement);
+             used_exp += GameData::builtin().exp_cost(res.recommended_enhancement);
              // Mastery cost is incremental from current (0) to recommended
              for m in (res.current_mastery + 1)..=res.recommended_mastery {
-                 used_hammers += hammer_cost(m);
+                 used_hammers += GameData::builtin().hammer_cost(m);
              }
         }
         assert!(used_exp <= 1000);
@@ -253,27 +375,28 @@ mod tests {
     }
     #[test]
     fn test_mythril_cost() {
-        assert_eq!(mythril_cost(119), 0);
-        assert_eq!(mythril_cost(120), 10);
-        assert_eq!(mythril_cost(121), 0);
-        assert_eq!(mythril_cost(140), 20);
-        assert_eq!(mythril_cost(160), 30);
-        assert_eq!(mythril_cost(180), 40);
-        assert_eq!(mythril_cost(200), 50);
+        assert_eq!(GameData::builtin().mythril_cost(119), 0);
+        assert_eq!(GameData::builtin().mythril_cost(120), 10);
+        assert_eq!(GameData::builtin().mythril_cost(121), 0);
+        assert_eq!(GameData::builtin().mythril_cost(140), 20);
+        assert_eq!(GameData::builtin().mythril_cost(160), 30);
+        assert_eq!(GameData::builtin().mythril_cost(180), 40);
+        assert_eq!(GameData::builtin().mythril_cost(200), 50);
     }
 
     #[test]
     fn test_mythril_gate() {
         let hero = HeroWeights {
             name: "TestMythril".to_string(),
-            gear: HeroGear {
-                helmet: Gear { mastery: 20, enhancement: 119 }, // Needs 10 mythril to go to 120
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 20, enhancement: 119, ..Default::default() }), // Needs 10 mythril to go to 120
                 // Max out others
-                gloves: Gear { mastery: 20, enhancement: 200 },
-                breastplate: Gear { mastery: 20, enhancement: 200 },
-                boots: Gear { mastery: 20, enhancement: 200 },
-            },
+                ("gloves", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("breastplate", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("boots", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+            ]),
             weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
         };
         
         // Case 1: Lots of EXP, 0 Mythril. Should stay at 119.
@@ -283,8 +406,9 @@ mod tests {
             hammers: 0,
             mythics: 0,
             mythril: 0,
+            ..Default::default()
         };
-        let output1: OptimizationOutput = serde_json::from_str(&solve(&serde_json::to_string(&input1).unwrap())).unwrap();
+        let output1: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input1).unwrap()));
         assert_eq!(output1.results[0].gear[0].recommended_enhancement, 119);
         
         // Case 2: Lots of EXP, 10 Mythril. Should go to 120+.
@@ -294,34 +418,36 @@ mod tests {
             hammers: 0,
             mythics: 100, // Added mythics to allow upgrade past 100
             mythril: 10,
+            ..Default::default()
         };
-        let output2: OptimizationOutput = serde_json::from_str(&solve(&serde_json::to_string(&input2).unwrap())).unwrap();
+        let output2: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input2).unwrap()));
         assert!(output2.results[0].gear[0].recommended_enhancement >= 120);
     }
     #[test]
     fn test_mythic_cost() {
-        assert_eq!(mythic_cost(100), 0);
-        assert_eq!(mythic_cost(101), 2);
-        assert_eq!(mythic_cost(102), 0);
-        assert_eq!(mythic_cost(120), 3);
-        assert_eq!(mythic_cost(140), 5);
-        assert_eq!(mythic_cost(160), 5);
-        assert_eq!(mythic_cost(180), 10);
-        assert_eq!(mythic_cost(200), 10);
+        assert_eq!(GameData::builtin().mythic_cost(100), 0);
+        assert_eq!(GameData::builtin().mythic_cost(101), 2);
+        assert_eq!(GameData::builtin().mythic_cost(102), 0);
+        assert_eq!(GameData::builtin().mythic_cost(120), 3);
+        assert_eq!(GameData::builtin().mythic_cost(140), 5);
+        assert_eq!(GameData::builtin().mythic_cost(160), 5);
+        assert_eq!(GameData::builtin().mythic_cost(180), 10);
+        assert_eq!(GameData::builtin().mythic_cost(200), 10);
     }
 
     #[test]
     fn test_mythic_gate() {
         let hero = HeroWeights {
             name: "TestMythic".to_string(),
-            gear: HeroGear {
-                helmet: Gear { mastery: 20, enhancement: 100 }, // Needs 2 mythics to go to 101
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 20, enhancement: 100, ..Default::default() }), // Needs 2 mythics to go to 101
                 // Max out others
-                gloves: Gear { mastery: 20, enhancement: 200 },
-                breastplate: Gear { mastery: 20, enhancement: 200 },
-                boots: Gear { mastery: 20, enhancement: 200 },
-            },
+                ("gloves", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("breastplate", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("boots", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+            ]),
             weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
         };
         
         // Case 1: Lots of EXP, 0 Mythics. Should stay at 100.
@@ -331,8 +457,9 @@ mod tests {
             hammers: 0,
             mythics: 0,
             mythril: 0,
+            ..Default::default()
         };
-        let output1: OptimizationOutput = serde_json::from_str(&solve(&serde_json::to_string(&input1).unwrap())).unwrap();
+        let output1: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input1).unwrap()));
         assert_eq!(output1.results[0].gear[0].recommended_enhancement, 100);
         
         // Case 2: Lots of EXP, 2 Mythics. Should go to 101+.
@@ -342,47 +469,49 @@ mod tests {
             hammers: 0,
             mythics: 2,
             mythril: 0,
+            ..Default::default()
         };
-        let output2: OptimizationOutput = serde_json::from_str(&solve(&serde_json::to_string(&input2).unwrap())).unwrap();
+        let output2: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input2).unwrap()));
         assert!(output2.results[0].gear[0].recommended_enhancement >= 101);
     }
 
     #[test]
     fn test_required_mastery() {
-        assert_eq!(required_mastery(100), 0);
-        assert_eq!(required_mastery(101), 10);
-        assert_eq!(required_mastery(119), 10);
-        assert_eq!(required_mastery(120), 11);
-        assert_eq!(required_mastery(139), 11);
-        assert_eq!(required_mastery(140), 12);
-        assert_eq!(required_mastery(159), 12);
-        assert_eq!(required_mastery(160), 13);
-        assert_eq!(required_mastery(179), 13);
-        assert_eq!(required_mastery(180), 14);
-        assert_eq!(required_mastery(199), 14);
-        assert_eq!(required_mastery(200), 15);
+        assert_eq!(GameData::builtin().required_mastery(100), 0);
+        assert_eq!(GameData::builtin().required_mastery(101), 10);
+        assert_eq!(GameData::builtin().required_mastery(119), 10);
+        assert_eq!(GameData::builtin().required_mastery(120), 11);
+        assert_eq!(GameData::builtin().required_mastery(139), 11);
+        assert_eq!(GameData::builtin().required_mastery(140), 12);
+        assert_eq!(GameData::builtin().required_mastery(159), 12);
+        assert_eq!(GameData::builtin().required_mastery(160), 13);
+        assert_eq!(GameData::builtin().required_mastery(179), 13);
+        assert_eq!(GameData::builtin().required_mastery(180), 14);
+        assert_eq!(GameData::builtin().required_mastery(199), 14);
+        assert_eq!(GameData::builtin().required_mastery(200), 15);
     }
 
     #[test]
     fn test_mastery_mythic_cost() {
-        assert_eq!(mastery_mythic_cost(10), 0);
-        assert_eq!(mastery_mythic_cost(11), 1);
-        assert_eq!(mastery_mythic_cost(12), 2);
-        assert_eq!(mastery_mythic_cost(20), 10);
+        assert_eq!(GameData::builtin().mastery_mythic_cost(10), 0);
+        assert_eq!(GameData::builtin().mastery_mythic_cost(11), 1);
+        assert_eq!(GameData::builtin().mastery_mythic_cost(12), 2);
+        assert_eq!(GameData::builtin().mastery_mythic_cost(20), 10);
     }
 
     #[test]
     fn test_mastery_mythic_gate() {
         let hero = HeroWeights {
             name: "TestMasteryMythic".to_string(),
-            gear: HeroGear {
-                helmet: Gear { mastery: 10, enhancement: 119 }, // Needs mastery 11 to go to 120. Mastery 11 costs 1 mythic.
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 10, enhancement: 119, ..Default::default() }), // Needs mastery 11 to go to 120. Mastery 11 costs 1 mythic.
                 // Max out others
-                gloves: Gear { mastery: 20, enhancement: 200 },
-                breastplate: Gear { mastery: 20, enhancement: 200 },
-                boots: Gear { mastery: 20, enhancement: 200 },
-            },
+                ("gloves", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("breastplate", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("boots", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+            ]),
             weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
         };
         
         // Case 1: Lots of EXP/Hammers/Mythril, 0 Mythics. Should stay at 119 because we can't afford mastery 11.
@@ -392,8 +521,9 @@ mod tests {
             hammers: 1000,
             mythics: 0,
             mythril: 100,
+            ..Default::default()
         };
-        let output1: OptimizationOutput = serde_json::from_str(&solve(&serde_json::to_string(&input1).unwrap())).unwrap();
+        let output1: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input1).unwrap()));
         assert_eq!(output1.results[0].gear[0].recommended_enhancement, 119);
         assert_eq!(output1.results[0].gear[0].recommended_mastery, 10);
         
@@ -410,8 +540,9 @@ mod tests {
             hammers: 1000,
             mythics: 1,
             mythril: 100,
+            ..Default::default()
         };
-        let output2: OptimizationOutput = serde_json::from_str(&solve(&serde_json::to_string(&input2).unwrap())).unwrap();
+        let output2: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input2).unwrap()));
         assert_eq!(output2.results[0].gear[0].recommended_enhancement, 119);
         assert_eq!(output2.results[0].gear[0].recommended_mastery, 11);
 
@@ -424,9 +555,1469 @@ mod tests {
             hammers: 1000,
             mythics: 4,
             mythril: 100,
+            ..Default::default()
         };
-        let output3: OptimizationOutput = serde_json::from_str(&solve(&serde_json::to_string(&input3).unwrap())).unwrap();
+        let output3: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input3).unwrap()));
         assert!(output3.results[0].gear[0].recommended_enhancement >= 120);
         assert!(output3.results[0].gear[0].recommended_mastery >= 11);
     }
+
+    #[test]
+    fn test_solve_exact_beats_greedy() {
+        let hero = HeroWeights {
+            name: "TestExact".to_string(),
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 13, enhancement: 125, ..Default::default() }),
+                ("gloves", Gear { mastery: 1, enhancement: 103, ..Default::default() }),
+                // Max out others
+                ("breastplate", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("boots", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+            ]),
+            weights: StatWeights { lethality: 0.5, health: 0.5 },
+            set_bonuses: Vec::new(),
+        };
+        let input = InputData {
+            heroes: vec![hero],
+            exp: 19483,
+            hammers: 694,
+            mythics: 18,
+            mythril: 53,
+            ..Default::default()
+        };
+
+        let greedy = solve_greedy(input.clone());
+        let exact_input = InputData { strategy: Strategy::Exact, ..input.clone() };
+        let json_output = solve(&serde_json::to_string(&exact_input).unwrap());
+        let exact: OptimizationOutput = unwrap_ok(&json_output);
+        assert_eq!(exact.strategy, Strategy::Exact);
+
+        let gap = exact.greedy_gap.as_ref().unwrap();
+        assert!(gap.proven_optimal);
+        assert!((gap.greedy_score - greedy.total_after_score).abs() < 1e-9);
+        assert!((gap.optimal_score - exact.total_after_score).abs() < 1e-9);
+        assert!(gap.absolute_gap > 0.0);
+        assert!(exact.total_after_score > greedy.total_after_score);
+
+        // The optimal plan must still fit the budget.
+        let mut used = Resources::default();
+        for res in &exact.results[0].gear {
+            let cost = model::upgrade_cost(
+                GameData::builtin(),
+                res.current_enhancement,
+                res.current_mastery,
+                res.recommended_enhancement,
+                res.recommended_mastery,
+            )
+            .unwrap();
+            used.exp += cost.exp;
+            used.hammers += cost.hammers;
+            used.mythics += cost.mythics;
+            used.mythril += cost.mythril;
+        }
+        assert!(used.exp <= input.exp);
+        assert!(used.hammers <= input.hammers);
+        assert!(used.mythics <= input.mythics);
+        assert!(used.mythril <= input.mythril);
+    }
+
+    #[test]
+    fn test_solve_exact_respects_gates() {
+        let hero = HeroWeights {
+            name: "TestExactGate".to_string(),
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 10, enhancement: 119, ..Default::default() }),
+                ("gloves", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("breastplate", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("boots", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+            ]),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+
+        // Mastery 11 plus the 120 gate needs 4 mythics, so 3 are not enough.
+        let input = InputData {
+            heroes: vec![hero],
+            exp: 1000000,
+            hammers: 1000,
+            mythics: 3,
+            mythril: 100,
+            ..Default::default()
+        };
+        let output = solve_exact(input);
+        assert_eq!(output.results[0].gear[0].recommended_enhancement, 119);
+        assert!(output.total_after_score >= output.greedy_gap.unwrap().greedy_score);
+    }
+
+    #[test]
+    fn test_solve_exact_proves_two_heroes() {
+        let output = solve_exact(alliance(2));
+        let gap = output.greedy_gap.unwrap();
+        assert!(gap.proven_optimal);
+        assert_eq!(gap.upper_bound, gap.optimal_score);
+    }
+
+    #[test]
+    fn test_solve_local_search() {
+        let hero = HeroWeights {
+            name: "TestLocalSearch".to_string(),
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 13, enhancement: 125, ..Default::default() }),
+                ("gloves", Gear { mastery: 1, enhancement: 103, ..Default::default() }),
+                ("breastplate", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("boots", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+            ]),
+            weights: StatWeights { lethality: 0.5, health: 0.5 },
+            set_bonuses: Vec::new(),
+        };
+        let input = InputData {
+            heroes: vec![hero],
+            exp: 19483,
+            hammers: 694,
+            mythics: 18,
+            mythril: 53,
+            strategy: Strategy::LocalSearch,
+            ..Default::default()
+        };
+
+        let greedy = solve_greedy(input.clone());
+        let output: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input).unwrap()));
+        assert_eq!(output.strategy, Strategy::LocalSearch);
+        assert!(output.runtime_ms >= 0.0);
+        assert!(output.total_after_score >= greedy.total_after_score);
+    }
+
+    #[test]
+    fn test_strategy_defaults_to_greedy() {
+        let json_input = r#"{"heroes":[],"exp":0,"hammers":0,"mythics":0,"mythril":0}"#;
+        let output: OptimizationOutput = unwrap_ok(&solve(json_input));
+        assert_eq!(output.strategy, Strategy::Greedy);
+    }
+
+    #[test]
+    fn test_upgrade_plan_gate_steps() {
+        let hero = HeroWeights {
+            name: "TestPlan".to_string(),
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 10, enhancement: 119, ..Default::default() }),
+                ("gloves", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("breastplate", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("boots", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+            ]),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+        let input = InputData {
+            heroes: vec![hero],
+            exp: 1000000,
+            hammers: 1000,
+            mythics: 4,
+            mythril: 100,
+            ..Default::default()
+        };
+        let output: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input).unwrap()));
+        let plan = &output.plan;
+
+        // Mastery 11 is bought first, then the 120 gate on its own, then the run to 139.
+        assert_eq!(plan.len(), 3);
+        assert_eq!(plan[0].action, PlanAction::Mastery);
+        assert_eq!((plan[0].from, plan[0].to), (10, 11));
+        assert_eq!(plan[0].cost, Resources { exp: 0, hammers: 110, mythics: 1, mythril: 0 });
+        assert_eq!(plan[1].action, PlanAction::Enhance);
+        assert_eq!((plan[1].from, plan[1].to), (119, 120));
+        assert_eq!(plan[1].cost, Resources { exp: 0, hammers: 0, mythics: 3, mythril: 10 });
+        assert_eq!(plan[1].balance.mythics, 0);
+        assert_eq!(plan[2].action, PlanAction::Enhance);
+        assert_eq!((plan[2].from, plan[2].to), (120, 139));
+        assert_eq!(plan[2].balance.exp, 1000000 - (GameData::builtin().exp_cost(139) - GameData::builtin().exp_cost(119)));
+        assert_eq!(output.results[0].gear[0].recommended_enhancement, 139);
+    }
+
+    #[test]
+    fn test_upgrade_plan_replays_to_results() {
+        let hero = HeroWeights {
+            name: "TestPlanReset".to_string(),
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 10, enhancement: 100, ..Default::default() }),
+                ("gloves", Gear { mastery: 0, enhancement: 20, ..Default::default() }),
+                ("breastplate", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+                ("boots", Gear { mastery: 0, enhancement: 0, ..Default::default() }),
+            ]),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+        for strategy in [Strategy::Greedy, Strategy::Exact, Strategy::LocalSearch] {
+            let input = InputData {
+                heroes: vec![hero.clone()],
+                exp: 0,
+                hammers: 100,
+                mythics: 0,
+                mythril: 0,
+                strategy,
+                ..Default::default()
+            };
+            let output: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input).unwrap()));
+
+            // The helmet ends below 100, so it is reset before anything is spent.
+            assert_eq!(output.plan[0].action, PlanAction::Reset);
+            assert_eq!(output.plan[0].gear_type, "helmet");
+            assert_eq!(output.plan[0].refund.exp, GameData::builtin().exp_cost(100));
+
+            let mut levels: Vec<(i32, i32)> = output.results[0]
+                .gear
+                .iter()
+                .map(|g| (g.current_enhancement, g.current_mastery))
+                .collect();
+            for step in &output.plan {
+                assert!(step.balance.is_non_negative());
+                let slot = output.results[0].gear.iter().position(|g| g.gear_type == step.gear_type).unwrap();
+                match step.action {
+                    PlanAction::Reset | PlanAction::Enhance => {
+                        assert_eq!(levels[slot].0, step.from);
+                        levels[slot].0 = step.to;
+                    }
+                    PlanAction::Mastery => {
+                        assert_eq!(levels[slot].1, step.from);
+                        levels[slot].1 = step.to;
+                    }
+                    PlanAction::Craft => levels[slot] = (step.to, 0),
+                }
+            }
+            for (level, res) in levels.iter().zip(&output.results[0].gear) {
+                assert_eq!(*level, (res.recommended_enhancement, res.recommended_mastery));
+            }
+        }
+    }
+
+    #[test]
+    fn test_resource_accounting() {
+        let hero = HeroWeights {
+            name: "TestAccounting".to_string(),
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 10, enhancement: 100, ..Default::default() }), // Reset, reclaims GameData::builtin().exp_cost(100)
+                ("gloves", Gear { mastery: 10, enhancement: 119, ..Default::default() }),
+                ("breastplate", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("boots", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+            ]),
+            weights: StatWeights { lethality: 0.1, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+        let input = InputData {
+            heroes: vec![hero],
+            exp: 5000,
+            hammers: 500,
+            mythics: 10,
+            mythril: 20,
+            ..Default::default()
+        };
+        let output: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input).unwrap()));
+        let res = &output.results[0];
+
+        assert_eq!(res.gear[0].reclaimed_exp, GameData::builtin().exp_cost(100));
+        assert_eq!(output.reclaimed_exp, GameData::builtin().exp_cost(100));
+        assert_eq!(res.gear[2].spent, Resources::default());
+
+        let spent = res.gear.iter().fold(Resources::default(), |acc, g| acc + g.spent);
+        assert_eq!(res.spent, spent);
+        assert_eq!(output.used, spent);
+        let budget = Resources { exp: 5000 + GameData::builtin().exp_cost(100), hammers: 500, mythics: 10, mythril: 20 };
+        assert_eq!(output.leftover, budget - spent);
+        assert!(output.leftover.is_non_negative());
+
+        // The plan ends with the same leftover.
+        assert_eq!(output.plan.last().unwrap().balance, output.leftover);
+
+        // A full budget plus the refund of a reset does not overflow.
+        let full = InputData { exp: i32::MAX, hammers: i32::MAX, mythics: i32::MAX, mythril: i32::MAX, ..input };
+        let output = solve_input(full).unwrap();
+        assert!(output.leftover.is_non_negative());
+        assert!(output.plan.iter().all(|step| step.balance.is_non_negative()));
+    }
+
+    #[test]
+    fn test_piece_constraints() {
+        let hero = HeroWeights {
+            name: "TestConstraints".to_string(),
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 5, enhancement: 60, locked: true, ..Default::default() }),
+                ("gloves", Gear { mastery: 0, enhancement: 40, no_reset: true, ..Default::default() }),
+                ("breastplate", Gear { mastery: 0, enhancement: 0, max_enhancement: Some(30), ..Default::default() }),
+                ("boots", Gear { mastery: 0, enhancement: 0, min_enhancement: Some(80), target_mastery: Some(3), ..Default::default() }),
+            ]),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+
+        for strategy in [Strategy::Greedy, Strategy::Exact, Strategy::LocalSearch] {
+            let input = InputData {
+                heroes: vec![hero.clone()],
+                exp: 200000,
+                hammers: 1000,
+                mythics: 0,
+                mythril: 0,
+                strategy,
+                ..Default::default()
+            };
+            let output: OptimizationOutput = unwrap_ok(&solve(&serde_json::to_string(&input).unwrap()));
+            let gear = &output.results[0].gear;
+
+            // Locked: untouched, not reset.
+            assert_eq!((gear[0].recommended_enhancement, gear[0].recommended_mastery), (60, 5));
+            assert_eq!(gear[0].reclaimed_exp, 0);
+            // No reset: can only go up.
+            assert!(gear[1].recommended_enhancement >= 40);
+            assert_eq!(gear[1].reclaimed_exp, 0);
+            // Capped.
+            assert!(gear[2].recommended_enhancement <= 30);
+            // Floor and pinned mastery.
+            assert!(gear[3].recommended_enhancement >= 80);
+            assert_eq!(gear[3].recommended_mastery, 3);
+            assert!(output.leftover.is_non_negative());
+        }
+    }
+
+    #[test]
+    fn test_reset_policy() {
+        let hero = HeroWeights {
+            name: "TestResetPolicy".to_string(),
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 0, enhancement: 100, ..Default::default() }),
+                ("gloves", Gear { mastery: 0, enhancement: 60, ..Default::default() }),
+                ("breastplate", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+                ("boots", Gear { mastery: 20, enhancement: 200, ..Default::default() }),
+            ]),
+            weights: StatWeights { lethality: 0.1, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+        let run = |reset_policy: ResetPolicy| -> OptimizationOutput {
+            let input = InputData {
+                heroes: vec![hero.clone()],
+                exp: 0,
+                reset_policy,
+                ..Default::default()
+            };
+            unwrap_ok(&solve(&serde_json::to_string(&input).unwrap()))
+        };
+
+        // Never: nothing to spend, nothing moves.
+        let never = run(ResetPolicy { mode: ResetMode::Never, ..Default::default() });
+        assert_eq!(never.reclaimed_exp, 0);
+        assert!(never.results[0].gear.iter().all(|g| !g.reset));
+        assert_eq!(never.results[0].gear[0].recommended_enhancement, 100);
+
+        // Below 80: only the gloves are reset, and they keep their level.
+        let below = run(ResetPolicy { mode: ResetMode::Below, threshold: 80, ..Default::default() });
+        assert_eq!(below.results[0].gear[0].recommended_enhancement, 100);
+        assert!(!below.results[0].gear[1].reset);
+
+        // Half refund: the helmet's EXP moves to the gloves at a loss.
+        let half = run(ResetPolicy { refund_percent: 50.0, ..Default::default() });
+        assert!(half.results[0].gear[0].reset);
+        assert_eq!(half.results[0].gear[0].reclaimed_exp, GameData::builtin().exp_cost(100) / 2);
+        assert_eq!(half.plan[0].refund.exp, GameData::builtin().exp_cost(100) / 2);
+        assert!(half.leftover.is_non_negative());
+
+        // Auto: the reset of the low-weight helmet pays off.
+        let auto = run(ResetPolicy { mode: ResetMode::Auto, ..Default::default() });
+        assert!(auto.results[0].gear[0].reset);
+        assert!(auto.total_after_score > never.total_after_score);
+    }
+
+    #[test]
+    fn test_game_data_validation() {
+        let builtin = GameData::builtin();
+        assert!(builtin.validate().is_ok());
+        assert_eq!(builtin.version, "2025.1");
+
+        let mut short = builtin.clone();
+        short.exp_costs.pop();
+        assert_eq!(short.validate().unwrap_err().field.as_deref(), Some("expCosts"));
+
+        let mut decreasing = builtin.clone();
+        decreasing.hammer_costs[5] = 0;
+        assert_eq!(decreasing.validate().unwrap_err().field.as_deref(), Some("hammerCosts[5]"));
+
+        let mut unordered = builtin.clone();
+        unordered.gates.swap(0, 1);
+        assert!(unordered.validate().is_err());
+
+        // A piece's total cost must fit in an amount.
+        let mut costly = builtin.clone();
+        *costly.hammer_costs.last_mut().unwrap() = i32::MAX;
+        assert_eq!(costly.validate().unwrap_err().field.as_deref(), Some("hammerCosts"));
+
+        let input = InputData { game_data: Some(short), ..Default::default() };
+        let error = solve_input(input).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Validation);
+        assert_eq!(error.field.as_deref(), Some("gameData.expCosts"));
+    }
+
+    #[test]
+    fn test_custom_game_data() {
+        let hero = HeroWeights {
+            name: "TestGameData".to_string(),
+            gear: HeroGear::default(),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+        let run = |game_data: Option<GameData>| -> OptimizationOutput {
+            let input = InputData {
+                heroes: vec![hero.clone()],
+                exp: 100_000,
+                game_data,
+                ..Default::default()
+            };
+            unwrap_ok(&solve(&serde_json::to_string(&input).unwrap()))
+        };
+
+        let default = run(None);
+        assert_eq!(default.game_data_version, "2025.1");
+
+        // A patch that halves every EXP cost buys more levels for the same EXP.
+        let mut patch = GameData::builtin().clone();
+        patch.version = "2025.2".to_string();
+        patch.exp_costs.iter_mut().for_each(|c| *c /= 2);
+        let patched = run(Some(patch));
+        assert_eq!(patched.game_data_version, "2025.2");
+        assert!(patched.total_after_score > default.total_after_score);
+        assert!(patched.leftover.is_non_negative());
+    }
+
+    #[test]
+    fn test_custom_gear_set() {
+        let mut game_data = GameData::builtin().clone();
+        let slot = |name: &str, stat| SlotDefinition { name: name.to_string(), stat };
+        game_data.gear_set = GearSet {
+            slots: vec![slot("weapon", SlotStat::Lethality), slot("armor", SlotStat::Health), slot("ring", SlotStat::Lethality)],
+            bonuses: vec![SetBonus {
+                name: "Full set +10".to_string(),
+                pieces: None,
+                enhancement: 10,
+                mastery: 0,
+                bonus: Stats { lethality: 0.5, health: 0.25 },
+            }],
+        };
+        let piece = |enhancement| Gear { enhancement, ..Default::default() };
+        let per_piece = game_data.stat(10, 0);
+        let full = HeroGear::from([("weapon", piece(10)), ("armor", piece(10)), ("ring", piece(10))]);
+        assert_eq!(game_data.calculate_stats(&full, &[]), Stats { lethality: 2.0 * per_piece + 0.5, health: per_piece + 0.25 });
+        // A missing slot is an empty piece, so the set is not complete.
+        let partial = HeroGear::from([("weapon", piece(10)), ("armor", piece(10))]);
+        let empty = game_data.stat(0, 0);
+        assert_eq!(game_data.calculate_stats(&partial, &[]), Stats { lethality: per_piece + empty, health: per_piece });
+
+        let input = InputData {
+            heroes: vec![HeroWeights {
+                name: "Custom".to_string(),
+                gear: partial,
+                weights: StatWeights { lethality: 1.0, health: 1.0 },
+                set_bonuses: Vec::new(),
+            }],
+            exp: 100_000,
+            game_data: Some(game_data.clone()),
+            ..Default::default()
+        };
+        let output = unwrap_ok(&solve(&serde_json::to_string(&input).unwrap()));
+        let result = &output.results[0];
+        let types: Vec<&str> = result.gear.iter().map(|g| g.gear_type.as_str()).collect();
+        assert_eq!(types, ["weapon", "armor", "ring"]);
+        assert!(result.gear.iter().all(|g| g.recommended_enhancement >= 10));
+        let levels = result.gear.iter().map(|g| PieceLevel {
+            tier: game_data.top_tier(),
+            enhancement: g.recommended_enhancement,
+            mastery: g.recommended_mastery,
+        });
+        assert_eq!(result.after_stats, game_data.stats_at(levels, &[]));
+        assert!(result.after_stats.health > game_data.stat(result.gear[1].recommended_enhancement, result.gear[1].recommended_mastery));
+
+        let mut unknown = input.clone();
+        unknown.heroes[0].gear.0.insert("helmet".to_string(), piece(0));
+        let error = solve_input(unknown).unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("heroes[0].gear.helmet"));
+
+        let mut duplicate = input;
+        let mut data = game_data;
+        data.gear_set.slots[2].name = "weapon".to_string();
+        duplicate.game_data = Some(data);
+        let error = solve_input(duplicate).unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("gameData.gearSet.slots[2].name"));
+    }
+
+    #[test]
+    fn test_set_bonus_milestone() {
+        let game_data = GameData::builtin();
+        let piece = |enhancement| Gear { enhancement, no_reset: true, ..Default::default() };
+        let bonus = SetBonus {
+            name: "Four at +20".to_string(),
+            pieces: None,
+            enhancement: 20,
+            mastery: 0,
+            bonus: Stats { lethality: 1.0, health: 0.0 },
+        };
+        let hero = HeroWeights {
+            name: "SetHero".to_string(),
+            gear: HeroGear::from([("helmet", piece(20)), ("gloves", piece(20)), ("breastplate", piece(20)), ("boots", piece(15))]),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: vec![bonus],
+        };
+        let before = game_data.calculate_stats(&hero.gear, &hero.set_bonuses);
+        assert_eq!(before, game_data.calculate_stats(&hero.gear, &[]));
+
+        let rookie = HeroWeights {
+            name: "Rookie".to_string(),
+            gear: HeroGear::default(),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+
+        // Exactly enough to take the boots to +20. One level at a time the
+        // bonus is out of sight and the rookie's levels are cheaper.
+        let exp = game_data.exp_cost(20) - game_data.exp_cost(15);
+        let input = InputData { heroes: vec![hero, rookie], exp, ..Default::default() };
+        for strategy in [Strategy::Greedy, Strategy::Exact, Strategy::LocalSearch] {
+            let output = solve_input(InputData { strategy, ..input.clone() }).unwrap();
+            let result = &output.results[0];
+            assert_eq!(result.set_bonuses, ["Four at +20"], "{:?}", strategy);
+            assert_eq!(result.gear[3].recommended_enhancement, 20);
+            assert!(result.after_stats.lethality > before.lethality + 1.0);
+        }
+
+        let mut invalid = input;
+        invalid.heroes[0].set_bonuses[0].pieces = Some(5);
+        let error = solve_input(invalid).unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("heroes[0].setBonuses[0].pieces"));
+    }
+
+    #[test]
+    fn test_solve_error_envelope() {
+        let error = |json: &str| -> SolverError {
+            match serde_json::from_str::<SolveResult>(&solve(json)).unwrap() {
+                SolveResult::Error(error) => error,
+                SolveResult::Ok(_) => panic!("expected an error for {}", json),
+            }
+        };
+
+        let ok: serde_json::Value = serde_json::from_str(&solve(r#"{"heroes": [], "exp": 10, "hammers": 0, "mythics": 0, "mythril": 0}"#)).unwrap();
+        assert!(ok["ok"]["results"].is_array());
+
+        let syntax = error("{not json");
+        assert_eq!(syntax.kind, ErrorKind::Parse);
+        assert_eq!(syntax.field, None);
+
+        let hero = |gear: &str, weights: &str| {
+            format!(
+                r#"{{"heroes": [{{"name": "A", "gear": {{"helmet": {}, "gloves": {{"mastery": 0, "enhancement": 0}}, "breastplate": {{"mastery": 0, "enhancement": 0}}, "boots": {{"mastery": 0, "enhancement": 0}}}}, "weights": {}}}], "exp": 0, "hammers": 0, "mythics": 0, "mythril": 0}}"#,
+                gear, weights
+            )
+        };
+        let wrong_type = error(&hero(r#"{"mastery": "ten", "enhancement": 0}"#, r#"{"lethality": 1, "health": 1}"#));
+        assert_eq!(wrong_type.kind, ErrorKind::Parse);
+        assert_eq!(wrong_type.field.as_deref(), Some("heroes[0].gear.helmet.mastery"));
+
+        let too_high = error(&hero(r#"{"mastery": 0, "enhancement": 201}"#, r#"{"lethality": 1, "health": 1}"#));
+        assert_eq!(too_high.kind, ErrorKind::Validation);
+        assert_eq!(too_high.field.as_deref(), Some("heroes[0].gear.helmet.enhancement"));
+
+        let mastery = error(&hero(r#"{"mastery": 21, "enhancement": 0}"#, r#"{"lethality": 1, "health": 1}"#));
+        assert_eq!(mastery.field.as_deref(), Some("heroes[0].gear.helmet.mastery"));
+
+        let negative = error(r#"{"heroes": [], "exp": 0, "hammers": -5, "mythics": 0, "mythril": 0}"#);
+        assert_eq!(negative.kind, ErrorKind::Validation);
+        assert_eq!(negative.field.as_deref(), Some("hammers"));
+
+        let input = InputData {
+            heroes: vec![HeroWeights {
+                name: "NaN".to_string(),
+                gear: HeroGear::default(),
+                weights: StatWeights { lethality: f64::NAN, health: 1.0 },
+                set_bonuses: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        let nan = solve_input(input).unwrap_err();
+        assert_eq!(nan.kind, ErrorKind::Validation);
+        assert_eq!(nan.field.as_deref(), Some("heroes[0].weights.lethality"));
+    }
+
+    #[test]
+    fn test_lenient_validation() {
+        let hero = HeroWeights {
+            name: "TestLenient".to_string(),
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 30, enhancement: 250, ..Default::default() }),
+                ("gloves", Gear { mastery: 0, enhancement: 10, min_enhancement: Some(50), max_enhancement: Some(40), ..Default::default() }),
+            ]),
+            weights: StatWeights { lethality: -1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+        let input = InputData { heroes: vec![hero], exp: -100, hammers: 50, ..Default::default() };
+
+        let strict = solve_input(input.clone()).unwrap_err();
+        assert_eq!(strict.kind, ErrorKind::Validation);
+        assert_eq!(strict.field.as_deref(), Some("exp"));
+
+        let output = solve_input(InputData { validation: ValidationMode::Lenient, ..input }).unwrap();
+        let fields: Vec<&str> = output.warnings.iter().map(|w| w.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "exp",
+                "heroes[0].weights.lethality",
+                "heroes[0].gear.helmet.enhancement",
+                "heroes[0].gear.helmet.mastery",
+                "heroes[0].gear.gloves.minEnhancement",
+            ]
+        );
+        let helmet = &output.results[0].gear[0];
+        assert_eq!(helmet.current_enhancement, 200);
+        assert_eq!(helmet.current_mastery, 20);
+        // No EXP to spend, and the hammers cannot buy past the mastery cap.
+        assert_eq!(output.used.exp, 0);
+        assert_eq!(helmet.recommended_mastery, 20);
+        assert!(output.leftover.is_non_negative());
+    }
+
+    #[test]
+    fn test_shadow_prices() {
+        let hero = HeroWeights {
+            name: "TestShadow".to_string(),
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 10, enhancement: 110, ..Default::default() }),
+                ("gloves", Gear { mastery: 10, enhancement: 110, ..Default::default() }),
+                ("breastplate", Gear { mastery: 10, enhancement: 110, ..Default::default() }),
+                ("boots", Gear { mastery: 10, enhancement: 110, ..Default::default() }),
+            ]),
+            weights: StatWeights { lethality: 1.0, health: 0.5 },
+            set_bonuses: Vec::new(),
+        };
+        let pack = Resources { exp: 20000, hammers: 200, ..Default::default() };
+        let input = InputData {
+            heroes: vec![hero],
+            exp: 10000,
+            hammers: 0,
+            mythics: 5,
+            mythril: 0,
+            shadow_prices: Some(ShadowPriceRequest {
+                bundles: vec![Bundle { name: "Pack".to_string(), contents: pack }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let output = solve_input(input.clone()).unwrap();
+        let prices = output.shadow_prices.clone().unwrap();
+
+        // More EXP and hammers both help; mythril is useless without the
+        // mythics and mastery needed to cross the next gate.
+        assert!(prices.exp > 0.0);
+        assert!(prices.hammers > 0.0);
+        assert_eq!(prices.mythril, 0.0);
+
+        // The bundle gain matches solving with the bundle added by hand.
+        let mut with_pack = InputData { shadow_prices: None, ..input.clone() };
+        with_pack.set_budget(with_pack.budget() + pack);
+        let expected = solve_input(with_pack).unwrap().total_after_score - output.total_after_score;
+        assert_eq!(prices.bundles.len(), 1);
+        assert!((prices.bundles[0].score_gain - expected).abs() < 1e-9);
+
+        // Near the top of the range only what still fits is added.
+        let output = solve_input(InputData { exp: i32::MAX - 647, ..input }).unwrap();
+        let prices = output.shadow_prices.unwrap();
+        assert_eq!(prices.step.exp, 647);
+        assert_eq!(prices.step.hammers, 200);
+    }
+
+    #[test]
+    fn test_shop_purchases() {
+        let hero = HeroWeights {
+            name: "TestShop".to_string(),
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 2, enhancement: 40, ..Default::default() }),
+                ("gloves", Gear { mastery: 2, enhancement: 40, ..Default::default() }),
+                ("breastplate", Gear { mastery: 2, enhancement: 40, ..Default::default() }),
+                ("boots", Gear { mastery: 2, enhancement: 40, ..Default::default() }),
+            ]),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+        let offer = |name: &str, price: i32, contents: Resources, max_quantity: i32| ShopOffer {
+            name: name.to_string(),
+            price,
+            contents,
+            max_quantity,
+        };
+        let shop = Shop {
+            gems: 300,
+            offers: vec![
+                offer("Hammers", 50, Resources { hammers: 100, ..Default::default() }, 5),
+                offer("Mythril", 100, Resources { mythril: 10, ..Default::default() }, 3),
+                offer("EXP", 30, Resources { exp: 2000, ..Default::default() }, 10),
+            ],
+        };
+        let input = InputData { heroes: vec![hero], exp: 5000, ..Default::default() };
+        let without = solve_input(input.clone()).unwrap();
+        let with = solve_input(InputData { shop: Some(shop.clone()), ..input.clone() }).unwrap();
+        let plan = with.shop.clone().unwrap();
+
+        assert!(with.total_after_score > without.total_after_score);
+        assert!(plan.gems_spent <= 300);
+        assert_eq!(plan.gems_spent + plan.gems_leftover, 300);
+        // Mythril is useless this far below the first gate.
+        assert!(plan.purchases.iter().all(|p| p.name != "Mythril"));
+        assert_eq!(plan.bought, plan.purchases.iter().fold(Resources::default(), |acc, p| acc + p.contents));
+        // The purchases are part of the budget that was spent.
+        assert_eq!(with.used + with.leftover, input.budget() + plan.bought + Resources { exp: with.reclaimed_exp, ..Default::default() });
+        assert!(with.leftover.is_non_negative());
+
+        // An offer that could take the budget past what an amount holds is
+        // rejected, or limited to the units that fit.
+        let mut huge = InputData { shop: Some(shop), ..input.clone() };
+        let offers = &mut huge.shop.as_mut().unwrap().offers;
+        offers.push(offer("Chest", 1, Resources { exp: 1_000_000, ..Default::default() }, 100_000));
+        let error = solve_input(huge.clone()).unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("shop.offers[3].maxQuantity"));
+        huge.validation = ValidationMode::Lenient;
+        let output = solve_input(huge).unwrap();
+        assert_eq!(output.warnings[0].field, "shop.offers[3].maxQuantity");
+        assert!(output.leftover.is_non_negative());
+
+        // A free offer with billions of units is bought only as far as it helps.
+        let free = Shop { gems: 0, offers: vec![offer("Free", 0, Resources { exp: 1, ..Default::default() }, 2_000_000_000)] };
+        let output = solve_input(InputData { shop: Some(free), ..input }).unwrap();
+        let bought = output.shop.unwrap().bought.exp;
+        assert!(bought > 0 && bought < 2_000_000_000);
+        assert!(output.leftover.exp < 1_000);
+    }
+
+    #[test]
+    fn test_exp_items() {
+        let hero = HeroWeights {
+            name: "TestExpItems".to_string(),
+            gear: HeroGear::from([
+                ("helmet", Gear { mastery: 0, enhancement: 0, no_reset: true, ..Default::default() }),
+                ("gloves", Gear { mastery: 0, enhancement: 0, no_reset: true, ..Default::default() }),
+                ("breastplate", Gear { mastery: 0, enhancement: 0, no_reset: true, ..Default::default() }),
+                ("boots", Gear { mastery: 0, enhancement: 0, no_reset: true, ..Default::default() }),
+            ]),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+        let stacks = vec![ExpItemStack { exp: 1000, count: 3 }, ExpItemStack { exp: 50, count: 7 }];
+        let input = InputData { heroes: vec![hero], exp: 20, exp_items: stacks.clone(), ..Default::default() };
+        let output = solve_input(input.clone()).unwrap();
+        let plan = output.exp_items.clone().unwrap();
+
+        let total: i32 = 20 + stacks.iter().map(|s| s.exp * s.count).sum::<i32>();
+        assert_eq!(output.used.exp + output.leftover.exp + plan.wasted_exp, total);
+        assert!(output.leftover.is_non_negative());
+
+        // Every piece gets exactly what it needs plus the overflow it loses.
+        for feed in &plan.feeds {
+            let piece = output.results[0].gear.iter().find(|g| g.gear_type == feed.gear_type).unwrap();
+            let fed: i32 = feed.items.iter().map(|s| s.exp * s.count).sum::<i32>() + feed.loose_exp;
+            assert_eq!(fed, piece.spent.exp + feed.wasted_exp);
+        }
+        // No more materials are used than the inventory holds.
+        for stack in &stacks {
+            let used: i32 = plan
+                .feeds
+                .iter()
+                .flat_map(|f| &f.items)
+                .filter(|s| s.exp == stack.exp)
+                .map(|s| s.count)
+                .sum();
+            let left: i32 = plan.leftover.iter().filter(|s| s.exp == stack.exp).map(|s| s.count).sum();
+            assert_eq!(used + left, stack.count);
+        }
+        assert!(plan.feeds.iter().map(|f| f.loose_exp).sum::<i32>() <= 20);
+
+        // Materials whose EXP does not fit in one total are rejected, or cut
+        // down to what fits.
+        let mut huge = input;
+        huge.exp_items.push(ExpItemStack { exp: 1_000_000, count: 5_000 });
+        let error = solve_input(huge.clone()).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Validation);
+        assert_eq!(error.field.as_deref(), Some("expItems[2].count"));
+        huge.validation = ValidationMode::Lenient;
+        let output = solve_input(huge).unwrap();
+        assert_eq!(output.warnings[0].field, "expItems[2].count");
+        assert!(output.leftover.is_non_negative());
+    }
+
+    #[test]
+    fn test_goal_seek() {
+        let game_data = GameData::builtin();
+        let hero = |name: &str| HeroWeights {
+            name: name.to_string(),
+            gear: HeroGear::default(),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+        let input = InputData { heroes: vec![hero("Cavalry"), hero("Infantry")], exp: 100_000, ..Default::default() };
+
+        // Piece targets cost exactly the upgrades to reach them.
+        let pieces = Goal {
+            pieces: vec![PieceTarget { hero_name: "Cavalry".to_string(), gear_type: None, enhancement: 140, mastery: 12 }],
+            ..Default::default()
+        };
+        let output = solve_input(InputData { goal: Some(pieces), ..input.clone() }).unwrap();
+        let goal = output.goal.clone().unwrap();
+        let per_piece = model::upgrade_cost(game_data, 0, 0, 140, 12).unwrap();
+        assert_eq!(goal.required, per_piece * 4);
+        assert_eq!(goal.shortfall.exp, per_piece.exp * 4 - 100_000);
+        assert_eq!(output.used, goal.required);
+        assert_eq!(output.leftover, Resources::default());
+        assert!(output.results[0].gear.iter().all(|g| g.recommended_enhancement == 140 && g.recommended_mastery == 12));
+        assert!(output.results[1].gear.iter().all(|g| g.recommended_enhancement == 0));
+        assert_eq!(output.plan.last().unwrap().balance, Resources::default());
+
+        // A health target is met by the infantry's health pieces only, and
+        // giving back any single level would miss it.
+        let target = 2.0 * game_data.stat(80, 0);
+        let scores = Goal {
+            scores: vec![ScoreTarget { hero_name: Some("Infantry".to_string()), stat: TargetStat::Health, at_least: target }],
+            ..Default::default()
+        };
+        let output = solve_input(InputData { goal: Some(scores), ..input.clone() }).unwrap();
+        let goal = output.goal.clone().unwrap();
+        assert!(goal.achieved[0] >= target - 1e-9);
+        let infantry = &output.results[1];
+        assert_eq!(infantry.after_stats.health, goal.achieved[0]);
+        assert_eq!(infantry.gear[0].recommended_enhancement, 0);
+        assert!(output.results[0].gear.iter().all(|g| g.recommended_enhancement == 0));
+        for g in [&infantry.gear[1], &infantry.gear[2]] {
+            let lower = infantry.after_stats.health - game_data.stat(g.recommended_enhancement, g.recommended_mastery)
+                + game_data.stat(g.recommended_enhancement - 1, g.recommended_mastery);
+            assert!(lower < target);
+        }
+
+        // Enhancement 140 needs mastery 12, which targetMastery forbids.
+        let mut capped = input.clone();
+        capped.heroes[0].gear = HeroGear::from([("helmet", Gear { target_mastery: Some(5), ..Default::default() })]);
+        let helmet = Goal {
+            pieces: vec![PieceTarget {
+                hero_name: "Cavalry".to_string(),
+                gear_type: Some("helmet".to_string()),
+                enhancement: 140,
+                mastery: 0,
+            }],
+            ..Default::default()
+        };
+        let error = solve_input(InputData { goal: Some(helmet), ..capped }).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Validation);
+        assert_eq!(error.field.as_deref(), Some("goal.pieces[0]"));
+
+        let unreachable = Goal {
+            scores: vec![ScoreTarget { hero_name: None, stat: TargetStat::Lethality, at_least: 1000.0 }],
+            ..Default::default()
+        };
+        let error = solve_input(InputData { goal: Some(unreachable), ..input }).unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("goal.scores[0].atLeast"));
+    }
+
+    #[test]
+    fn test_schedule() {
+        let game_data = GameData::builtin();
+        let gate = game_data.gate(101).unwrap().clone();
+        let piece = Gear { enhancement: 100, mastery: game_data.required_mastery(101), ..Default::default() };
+        let hero = HeroWeights {
+            name: "Cavalry".to_string(),
+            gear: HeroGear::from([("helmet", piece.clone()), ("gloves", piece.clone()), ("breastplate", piece.clone()), ("boots", piece)]),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+        let income = Resources { exp: 0, hammers: 0, mythics: gate.mythics, mythril: gate.mythril };
+        let input = InputData {
+            heroes: vec![hero],
+            exp: 1_000_000,
+            schedule: Some(Schedule { periods: 4, income }),
+            ..Default::default()
+        };
+        let output = solve_input(input.clone()).unwrap();
+        assert_eq!(output.schedule.len(), 4);
+
+        // Nothing can cross the gate before the first income arrives.
+        let first = &output.schedule[0];
+        assert!(first.output.results[0].gear.iter().all(|g| g.recommended_enhancement == 100));
+        assert!(first.waiting_for.iter().any(|step| step.action == PlanAction::Enhance && step.from == 100));
+
+        let mut leftover = Resources { exp: 1_000_000, ..Resources::default() };
+        for (k, period) in output.schedule.iter().enumerate() {
+            let snapshot = &period.output;
+            assert_eq!(snapshot.leftover, leftover + period.income - snapshot.used);
+            assert!(snapshot.leftover.is_non_negative());
+            assert!(snapshot.plan.iter().all(|step| step.balance.is_non_negative()));
+            leftover = snapshot.leftover;
+            if let Some(next) = output.schedule.get(k + 1) {
+                for (now, then) in snapshot.results[0].gear.iter().zip(&next.output.results[0].gear) {
+                    assert_eq!(now.recommended_enhancement, then.current_enhancement);
+                    assert_eq!(now.recommended_mastery, then.current_mastery);
+                }
+            }
+        }
+
+        // By the end the horizon plan is done.
+        let last = &output.schedule[3];
+        assert!(last.waiting_for.is_empty());
+        for (done, planned) in last.output.results[0].gear.iter().zip(&output.results[0].gear) {
+            assert_eq!(done.recommended_enhancement, planned.recommended_enhancement);
+        }
+        assert_eq!(last.output.leftover, output.leftover);
+        assert_eq!(output.results[0].gear.iter().filter(|g| g.recommended_enhancement > 100).count(), 3);
+
+        // The horizon is bounded, and so is the income it adds up.
+        let too_long = InputData { schedule: Some(Schedule { periods: 1_000_000, income }), ..input.clone() };
+        let error = solve_input(too_long.clone()).unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("schedule.periods"));
+        let output = solve_input(InputData { validation: ValidationMode::Lenient, ..too_long }).unwrap();
+        assert_eq!(output.warnings[0].field, "schedule.periods");
+        assert_eq!(output.schedule.len(), 260);
+        let rich = Schedule { periods: 4, income: Resources { exp: 1_000_000_000, ..income } };
+        let error = solve_input(InputData { schedule: Some(rich), ..input }).unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("schedule.income.exp"));
+    }
+
+    #[test]
+    fn test_pareto_frontier() {
+        let hero = |name: &str| HeroWeights {
+            name: name.to_string(),
+            gear: HeroGear::default(),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+        let input = InputData {
+            heroes: vec![hero("Cavalry"), hero("Infantry")],
+            exp: 150_000,
+            pareto: Some(ParetoRequest::default()),
+            ..Default::default()
+        };
+        let output = solve_input(input.clone()).unwrap();
+        let frontier = &output.pareto;
+        assert!(frontier.len() >= 3, "{} points", frontier.len());
+        for pair in frontier.windows(2) {
+            assert!(pair[0].lethality > pair[1].lethality);
+            assert!(pair[0].health < pair[1].health);
+            assert!(pair[0].weights.lethality > pair[1].weights.lethality);
+        }
+        for point in frontier {
+            assert!((point.weights.lethality + point.weights.health - 1.0).abs() < 1e-9);
+            let lethality: f64 = point.heroes.iter().map(|hero| hero.lethality).sum();
+            assert!((point.lethality - lethality).abs() < 1e-9);
+            assert!(point.output.leftover.is_non_negative());
+        }
+        // The balanced solve is on the frontier or behind it.
+        let after = output.results.iter().fold((0.0, 0.0), |(l, h), r| (l + r.after_stats.lethality, h + r.after_stats.health));
+        assert!(frontier.iter().any(|point| point.lethality >= after.0 - 1e-9 && point.health >= after.1 - 1e-9));
+
+        // The two ends are always solved, so fewer than two points is an error.
+        let single = InputData { pareto: Some(ParetoRequest { max_points: 1 }), ..input.clone() };
+        let error = solve_input(single.clone()).unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("pareto.maxPoints"));
+        let output = solve_input(InputData { validation: ValidationMode::Lenient, ..single }).unwrap();
+        assert_eq!(output.warnings[0].field, "pareto.maxPoints");
+        assert_eq!(output.pareto.len(), 2);
+        let two = solve_input(InputData { pareto: Some(ParetoRequest { max_points: 2 }), ..input }).unwrap();
+        assert!(two.pareto.len() <= 2);
+    }
+
+    #[test]
+    fn test_objectives() {
+        let strong = Gear { enhancement: 120, mastery: 11, ..Default::default() };
+        let hero = |name: &str, gear: Gear| HeroWeights {
+            name: name.to_string(),
+            gear: HeroGear::from([("helmet", gear.clone()), ("gloves", gear.clone()), ("breastplate", gear.clone()), ("boots", gear)]),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+        let input = InputData {
+            heroes: vec![hero("Veteran", strong), hero("Recruit", Gear::default())],
+            exp: 1_000,
+            hammers: 100,
+            mythics: 0,
+            mythril: 0,
+            ..Default::default()
+        };
+
+        for objective in [Objective::Linear, Objective::Multiplicative, Objective::LogUtility, Objective::MaxMin] {
+            let solve_with = |strategy| solve_input(InputData { objective, strategy, ..input.clone() }).unwrap();
+            let greedy = solve_with(Strategy::Greedy);
+            let exact = solve_with(Strategy::Exact);
+            let local = solve_with(Strategy::LocalSearch);
+
+            // Reported scores are the objective of the reported stats.
+            let values = greedy.results.iter().map(|r| objective.hero_value(&StatWeights { lethality: 1.0, health: 1.0 }, &r.after_stats));
+            assert!((greedy.total_after_score - objective.combine(values)).abs() < 1e-9, "{:?}", objective);
+
+            let gap = exact.greedy_gap.as_ref().unwrap();
+            assert!(gap.proven_optimal, "{:?}", objective);
+            assert!((gap.greedy_score - greedy.total_after_score).abs() < 1e-9, "{:?}", objective);
+            assert!(exact.total_after_score >= greedy.total_after_score - 1e-9, "{:?}", objective);
+            assert!(exact.total_after_score >= local.total_after_score - 1e-9, "{:?}", objective);
+            assert!(local.total_after_score >= greedy.total_after_score - 1e-9, "{:?}", objective);
+            for output in [&greedy, &exact, &local] {
+                assert!(output.leftover.is_non_negative());
+            }
+        }
+
+        // The sum favours the hero with the larger weights; max-min fairness
+        // lifts the other one.
+        let mut heroes = vec![hero("Veteran", Gear::default()), hero("Recruit", Gear::default())];
+        heroes[0].weights = StatWeights { lethality: 3.0, health: 3.0 };
+        let weakest = |objective| {
+            let output = solve_input(InputData { objective, heroes: heroes.clone(), ..input.clone() }).unwrap();
+            output.results.iter().map(|r| r.after_score).fold(f64::INFINITY, f64::min)
+        };
+        assert!(weakest(Objective::MaxMin) > weakest(Objective::Linear));
+    }
+
+    #[test]
+    fn test_gear_tiers_and_crafting() {
+        let mut epic = GameData::builtin().clone();
+        epic.tier = Some("epic".to_string());
+        epic.max_enhancement = 100;
+        epic.exp_costs.truncate(101);
+        epic.gates.retain(|gate| gate.level <= 100);
+        epic.stat.segments.retain(|segment| segment.up_to <= 100);
+        epic.stat.base /= 2.0;
+        for segment in &mut epic.stat.segments {
+            segment.per_level /= 2.0;
+        }
+        let mut game_data = GameData::builtin().clone();
+        game_data.tier = Some("legendary".to_string());
+        game_data.craft_cost = Some(Resources { exp: 1_000, mythics: 2, ..Default::default() });
+        game_data.tiers = vec![epic.clone()];
+
+        let piece = |tier: &str, enhancement| Gear { tier: Some(tier.to_string()), enhancement, ..Default::default() };
+        let gear = HeroGear::from([
+            ("helmet", piece("epic", 40)),
+            ("boots", piece("legendary", 40)),
+            ("breastplate", piece("epic", 40)),
+            ("gloves", Gear { enhancement: 40, ..Default::default() }),
+        ]);
+        // Each piece uses the curve of its own tier; no tier is the top one.
+        let stats = game_data.calculate_stats(&gear, &[]);
+        assert!((stats.health - epic.stat(40, 0) - game_data.stat(40, 0)).abs() < 1e-12);
+        assert!((stats.lethality - epic.stat(40, 0) - game_data.stat(40, 0)).abs() < 1e-12);
+
+        let input = InputData {
+            heroes: vec![HeroWeights {
+                name: "Mixed".to_string(),
+                gear,
+                weights: StatWeights { lethality: 1.0, health: 1.0 },
+                set_bonuses: Vec::new(),
+            }],
+            exp: 2_000_000,
+            hammers: 2_000,
+            mythics: 50,
+            mythril: 50,
+            game_data: Some(game_data.clone()),
+            ..Default::default()
+        };
+        // Epic pieces stop at their own cap.
+        let output = solve_input(input.clone()).unwrap();
+        assert!(output.results[0].gear[0].recommended_enhancement <= 100);
+        assert!(output.crafting.is_empty());
+
+        let output = solve_input(InputData { allow_crafting: true, ..input.clone() }).unwrap();
+        assert_eq!(output.crafting.len(), 2);
+        for option in &output.crafting {
+            assert_eq!(option.from_tier.as_deref(), Some("epic"));
+            assert_eq!(option.to_tier, "legendary");
+        }
+        let crafted: Vec<&GearResult> = output.results[0].gear.iter().filter(|g| g.crafted.is_some()).collect();
+        assert_eq!(crafted.len(), output.crafting.iter().filter(|o| o.chosen).count());
+        assert!(!crafted.is_empty());
+        assert!(output.total_after_score > output.crafting[0].enhance_score);
+        assert_eq!(output.plan[0].action, PlanAction::Craft);
+        assert_eq!(output.plan[0].from, 40);
+        assert!(output.leftover.is_non_negative());
+        assert_eq!(output.used + output.leftover, input.budget());
+
+        // A noReset piece is never given up, and a crafted piece keeps its caps.
+        let mut constrained = InputData { allow_crafting: true, ..input.clone() };
+        constrained.heroes[0].gear.0.get_mut("breastplate").unwrap().no_reset = true;
+        let output = solve_input(constrained).unwrap();
+        assert_eq!(output.crafting.len(), 1);
+        assert_eq!(output.crafting[0].gear_type, "helmet");
+        let (crafts, _) = craft::plan_crafts(&InputData { allow_crafting: true, ..input.clone() });
+        assert!(!crafts.is_empty());
+        let mut capped = input.clone();
+        for slot in ["helmet", "breastplate"] {
+            let gear = capped.heroes[0].gear.0.get_mut(slot).unwrap();
+            gear.max_enhancement = Some(90);
+            gear.target_mastery = Some(3);
+        }
+        let crafted = craft::apply_crafts(&capped, &crafts);
+        let new: Vec<&Gear> = crafted.heroes[0].gear.0.values().filter(|g| g.enhancement == 0).collect();
+        assert_eq!(new.len(), crafts.len());
+        for gear in new {
+            assert_eq!(gear.tier.as_deref(), Some("legendary"));
+            assert_eq!((gear.max_enhancement, gear.target_mastery, gear.mastery), (Some(90), Some(3), 0));
+        }
+
+        let mut unknown = input;
+        unknown.heroes[0].gear.0.get_mut("helmet").unwrap().tier = Some("mythic".to_string());
+        let error = solve_input(unknown).unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("heroes[0].gear.helmet.tier"));
+    }
+
+    #[test]
+    fn test_solve_batch() {
+        let player = |name: &str, enhancement, mastery, exp| PlayerInput {
+            name: name.to_string(),
+            input: InputData {
+                heroes: vec![HeroWeights {
+                    name: "Main".to_string(),
+                    gear: HeroGear::from(
+                        ["helmet", "gloves", "breastplate", "boots"]
+                            .map(|slot| (slot, Gear { enhancement, mastery, no_reset: true, ..Default::default() })),
+                    ),
+                    weights: StatWeights { lethality: 1.0, health: 1.0 },
+                    set_bonuses: Vec::new(),
+                }],
+                exp,
+                ..Default::default()
+            },
+        };
+        let mut broken = player("Broken", 0, 0, 0);
+        broken.input.exp = -1;
+        let batch = BatchInput {
+            players: vec![player("Gated", 100, 10, 1_000_000), player("Rookie", 0, 0, 5_000), broken],
+            mythic_step: 5,
+        };
+        let json = solve_batch(&serde_json::to_string(&batch).unwrap());
+        let output: BatchOutput = Result::from(serde_json::from_str::<SolveResult<BatchOutput>>(&json).unwrap()).unwrap();
+
+        let names: Vec<&str> = output.players.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Gated", "Rookie", "Broken"]);
+        let SolveResult::Error(error) = &output.players[2].result else { panic!("negative exp is rejected") };
+        assert_eq!(error.field.as_deref(), Some("exp"));
+
+        let summary = &output.summary;
+        assert_eq!(summary.solved, 2);
+        assert_eq!(summary.failed, ["Broken"]);
+        let scores: Vec<(f64, f64)> = output.players[..2]
+            .iter()
+            .map(|p| match &p.result {
+                SolveResult::Ok(out) => (out.total_before_score, out.total_after_score),
+                SolveResult::Error(error) => panic!("{}", error),
+            })
+            .collect();
+        let gain: f64 = scores.iter().map(|(before, after)| after - before).sum();
+        assert!((summary.total_score_gain - gain).abs() < 1e-9);
+        assert!(summary.total_score_gain > 0.0);
+        // Only the player stuck at the first gate needs mythics.
+        assert_eq!(summary.mythic_blocked.len(), 1);
+        assert_eq!(summary.mythic_blocked[0].name, "Gated");
+        assert!(summary.mythic_blocked[0].score_gain > 0.0);
+
+        let levels = &summary.enhancement_levels;
+        assert_eq!(levels[0].from, 0);
+        assert!(levels.windows(2).all(|w| w[1].from == w[0].to + 1));
+        assert_eq!(levels.iter().map(|b| b.current).sum::<usize>(), 8);
+        assert_eq!(levels.iter().map(|b| b.recommended).sum::<usize>(), 8);
+        assert_eq!(levels[5], LevelBucket { from: 100, to: 119, current: 4, recommended: 4 });
+
+        let error = Result::from(serde_json::from_str::<SolveResult<BatchOutput>>(&solve_batch("{}")).unwrap()).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Parse);
+    }
+
+    #[test]
+    fn test_verify_plan() {
+        let piece = |enhancement, mastery| Gear { enhancement, mastery, ..Default::default() };
+        let input = InputData {
+            heroes: vec![HeroWeights {
+                name: "Checked".to_string(),
+                gear: HeroGear::from([
+                    ("helmet", piece(60, 0)),
+                    ("gloves", piece(100, 10)),
+                    ("breastplate", piece(0, 0)),
+                    ("boots", Gear { locked: true, ..piece(150, 12) }),
+                ]),
+                weights: StatWeights { lethality: 1.0, health: 1.5 },
+                set_bonuses: Vec::new(),
+            }],
+            exp: 400_000,
+            hammers: 2_000,
+            mythics: 30,
+            mythril: 40,
+            ..Default::default()
+        };
+        // The accounting of every strategy agrees with the oracle.
+        for strategy in [Strategy::Greedy, Strategy::Exact, Strategy::LocalSearch] {
+            let output = solve_input(InputData { strategy, ..input.clone() }).unwrap();
+            let gear: Vec<HeroGear> = output
+                .results
+                .iter()
+                .map(|result| {
+                    HeroGear(
+                        result
+                            .gear
+                            .iter()
+                            .map(|g| (g.gear_type.clone(), piece(g.recommended_enhancement, g.recommended_mastery)))
+                            .collect(),
+                    )
+                })
+                .collect();
+            let check = verify_plan(&input, &gear).unwrap();
+            assert!(check.feasible, "{:?}: {:?}", strategy, check.violations);
+            assert_eq!(check.required, output.used, "{:?}", strategy);
+            assert_eq!(check.reclaimed_exp, output.reclaimed_exp, "{:?}", strategy);
+            assert_eq!(check.shortfall, Resources::default());
+        }
+
+        // Crossing gate 101 costs its mythics and needs mastery 10, which the
+        // gloves already have.
+        let proposal = |pieces: &[(&str, Gear)]| vec![HeroGear(pieces.iter().map(|(k, g)| (k.to_string(), g.clone())).collect())];
+        let check = verify_plan(&input, &proposal(&[("gloves", piece(101, 10))])).unwrap();
+        assert!(check.feasible);
+        let game_data = GameData::builtin();
+        assert_eq!(check.required.exp, game_data.exp_cost(101) - game_data.exp_cost(100));
+        assert_eq!(check.required.mythics, game_data.mythic_cost(101));
+
+        let check = verify_plan(
+            &input,
+            &proposal(&[
+                ("helmet", piece(120, 5)),
+                ("gloves", piece(100, 9)),
+                ("breastplate", piece(201, 0)),
+                ("boots", piece(151, 12)),
+                ("ring", piece(0, 0)),
+            ]),
+        )
+        .unwrap();
+        assert!(!check.feasible);
+        let fields: Vec<&str> = check.violations.iter().map(|v| v.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "heroes[0].gear.ring",
+                "heroes[0].gear.helmet.mastery",
+                "heroes[0].gear.gloves.mastery",
+                "heroes[0].gear.breastplate.enhancement",
+                "heroes[0].gear.boots.enhancement",
+            ]
+        );
+
+        // Under the default policy only pieces below 101 may be reset.
+        let mut high = input.clone();
+        high.heroes[0].gear.0.insert("helmet".to_string(), piece(150, 12));
+        let check = verify_plan(&high, &proposal(&[("helmet", piece(100, 12))])).unwrap();
+        assert!(!check.feasible);
+        assert_eq!(check.reclaimed_exp, 0);
+        let fields: Vec<&str> = check.violations.iter().map(|v| v.field.as_str()).collect();
+        assert_eq!(fields, ["heroes[0].gear.helmet.enhancement"]);
+        high.reset_policy.threshold = 151;
+        assert!(verify_plan(&high, &proposal(&[("helmet", piece(100, 12))])).unwrap().feasible);
+
+        // A slot left out stays as it is, which must still meet its floors.
+        let mut floors = input.clone();
+        floors.heroes[0].gear.0.insert("helmet".to_string(), Gear { min_enhancement: Some(80), ..piece(60, 0) });
+        floors.heroes[0].gear.0.insert("breastplate".to_string(), Gear { target_mastery: Some(3), ..piece(0, 0) });
+        let check = verify_plan(&floors, &proposal(&[("gloves", piece(101, 10))])).unwrap();
+        assert!(!check.feasible);
+        let fields: Vec<&str> = check.violations.iter().map(|v| v.field.as_str()).collect();
+        assert_eq!(fields, ["heroes[0].gear.helmet.enhancement", "heroes[0].gear.breastplate.mastery"]);
+
+        let check = verify_plan(&InputData { exp: 1_000, ..input.clone() }, &proposal(&[("breastplate", piece(100, 0))])).unwrap();
+        assert_eq!(check.violations.len(), 1);
+        assert_eq!(check.violations[0].field, "exp");
+        assert_eq!(check.shortfall.exp, game_data.exp_cost(100) - 1_000);
+
+        // Through the JSON entry point, with the input still validated.
+        let json = serde_json::to_string(&PlanProposal { input: InputData { exp: -1, ..input }, gear: Vec::new() }).unwrap();
+        let result: SolveResult<PlanCheck> = serde_json::from_str(&verify(&json)).unwrap();
+        let error = Result::from(result).unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("input.exp"));
+    }
+
+    #[test]
+    fn test_cost_between() {
+        let game_data = GameData::builtin();
+        let gear = |enhancement, mastery| Gear { enhancement, mastery, ..Default::default() };
+
+        let quote = cost_between(game_data, &gear(118, 10), &gear(140, 12)).unwrap();
+        let levels: Vec<i32> = quote.gates.iter().map(|gate| gate.level).collect();
+        assert_eq!(levels, [120, 140]);
+        assert_eq!(quote.mastery, 12);
+        assert_eq!(
+            quote.cost,
+            Resources {
+                exp: game_data.exp_cost(140) - game_data.exp_cost(118),
+                hammers: game_data.hammer_cost(11) + game_data.hammer_cost(12),
+                // Gates 120 and 140, then mastery 11 and 12.
+                mythics: 3 + 5 + 1 + 2,
+                mythril: 10 + 20,
+            }
+        );
+
+        // Gate 101 needs mastery 10, which is bought along.
+        let quote = cost_between(game_data, &gear(100, 0), &gear(101, 0)).unwrap();
+        assert_eq!(quote.mastery, 10);
+        assert_eq!(quote.cost.hammers, (1..=10).map(|m| game_data.hammer_cost(m)).sum::<i32>());
+        assert_eq!(quote.cost.mythics, 2);
+
+        let error = cost_between(game_data, &gear(50, 0), &gear(40, 0)).unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("to.enhancement"));
+        let error = cost_between(game_data, &gear(50, 0), &gear(60, 21)).unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("to.mastery"));
+
+        let query = CostQuery { from: gear(118, 10), to: gear(140, 12), game_data: None };
+        let result: SolveResult<TransitionCost> = serde_json::from_str(&cost(&serde_json::to_string(&query).unwrap())).unwrap();
+        assert_eq!(Result::from(result).unwrap(), cost_between(game_data, &query.from, &query.to).unwrap());
+    }
+
+    #[test]
+    fn test_bottlenecks() {
+        let gear = |enhancement, mastery| Gear { enhancement, mastery, no_reset: true, ..Default::default() };
+        let input = InputData {
+            heroes: vec![HeroWeights {
+                name: "Stuck".to_string(),
+                gear: HeroGear::from([
+                    ("helmet", gear(100, 0)),
+                    ("gloves", Gear { target_mastery: Some(5), ..gear(100, 5) }),
+                    ("breastplate", gear(200, 15)),
+                    ("boots", gear(200, 20)),
+                ]),
+                weights: StatWeights { lethality: 1.0, health: 1.0 },
+                set_bonuses: Vec::new(),
+            }],
+            exp: 1_000_000,
+            ..Default::default()
+        };
+        let output = solve_input(input.clone()).unwrap();
+        let messages: Vec<(&str, &str)> =
+            output.bottlenecks.iter().map(|b| (b.gear_type.as_str(), b.message.as_str())).collect();
+        assert_eq!(
+            messages,
+            [
+                ("helmet", "blocked at 100→101: need 2 mythics + mastery 10 (costs 550 hammers)"),
+                ("gloves", "blocked at 100→101: need 2 mythics + mastery 10, above targetMastery 5"),
+                ("breastplate", "blocked at mastery 15→16: need 160 hammers + 6 mythics"),
+            ]
+        );
+        let helmet = &output.bottlenecks[0];
+        assert_eq!((helmet.action, helmet.from, helmet.to, helmet.required_mastery), (PlanAction::Enhance, 100, 101, Some(10)));
+        assert_eq!(helmet.missing, Resources { hammers: 550, mythics: 2, ..Default::default() });
+
+        let ranking: Vec<(&str, usize, i32)> =
+            output.resource_ranking.iter().map(|r| (r.resource.as_str(), r.blocked, r.missing)).collect();
+        assert_eq!(ranking, [("hammers", 3, 550 + 400 + 160), ("mythics", 3, 2 + 2 + 6)]);
+
+        // With enough of everything only the constraint is left.
+        let rich = solve_input(InputData { hammers: 10_000, mythics: 500, mythril: 500, ..input }).unwrap();
+        assert_eq!(rich.bottlenecks.len(), 1);
+        assert_eq!(rich.bottlenecks[0].message, "blocked at 100→101: need mastery 10, above targetMastery 5");
+        assert!(rich.resource_ranking.is_empty());
+
+        // What is missing in total saturates instead of overflowing.
+        let mut costly = GameData::builtin().clone();
+        for cost in &mut costly.exp_costs[1..] {
+            *cost = 2_000_000_000;
+        }
+        let hero = |name: &str| HeroWeights {
+            name: name.to_string(),
+            gear: HeroGear::default(),
+            weights: StatWeights { lethality: 1.0, health: 1.0 },
+            set_bonuses: Vec::new(),
+        };
+        let broke = InputData { heroes: vec![hero("A"), hero("B")], game_data: Some(costly), ..Default::default() };
+        let output = solve_input(broke).unwrap();
+        assert_eq!(output.resource_ranking[0].resource, "exp");
+        assert_eq!((output.resource_ranking[0].blocked, output.resource_ranking[0].missing), (8, i32::MAX));
+    }
+
+    /// A reproducible roster of `heroes` members with assorted gear, weights
+    /// and a budget that grows with it.
+    fn alliance(heroes: usize) -> InputData {
+        let game_data = GameData::builtin();
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = |below: i32| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((seed >> 33) % below as u64) as i32
+        };
+        let heroes = (0..heroes)
+            .map(|h| {
+                let mut piece = || {
+                    let enhancement = next(161);
+                    let mastery = next(11).max(game_data.required_mastery(enhancement));
+                    Gear { enhancement, mastery, no_reset: true, ..Default::default() }
+                };
+                HeroWeights {
+                    name: format!("Member {}", h + 1),
+                    gear: HeroGear::from([("helmet", piece()), ("gloves", piece()), ("breastplate", piece()), ("boots", piece())]),
+                    weights: StatWeights { lethality: 0.5 + next(100) as f64 / 100.0, health: 0.5 + next(100) as f64 / 100.0 },
+                    set_bonuses: Vec::new(),
+                }
+            })
+            .collect::<Vec<_>>();
+        let members = heroes.len() as i32;
+        InputData {
+            heroes,
+            exp: 300_000 * members,
+            hammers: 1_000 * members,
+            mythics: 20 * members,
+            mythril: 20 * members,
+            ..Default::default()
+        }
+    }
+
+    /// The queued greedy loop against the rescanning one it replaced.
+    fn greedy_both_ways(input: &InputData) -> (Vec<plan::Upgrade>, Vec<plan::Upgrade>) {
+        let game_data = input.game_data();
+        let (mut items, budget) = model::prepare_items(input);
+        let (_, budget) = greedy::raise_to_floors(game_data, &mut items, budget);
+        let mut reference = items.clone();
+        let queued = greedy::run_greedy(game_data, input.objective, &mut items, budget);
+        let rescanned = greedy::run_greedy_rescan(game_data, input.objective, &mut reference, budget);
+        (queued, rescanned)
+    }
+
+    #[test]
+    fn test_greedy_queue_matches_rescan() {
+        let mut with_bonus = GameData::builtin().clone();
+        with_bonus.gear_set.bonuses.push(SetBonus {
+            name: "Two at +150".to_string(),
+            pieces: Some(2),
+            enhancement: 150,
+            mastery: 0,
+            bonus: Stats { lethality: 0.1, health: 0.1 },
+        });
+        for objective in [Objective::Linear, Objective::Multiplicative, Objective::LogUtility, Objective::MaxMin] {
+            for game_data in [None, Some(with_bonus.clone())] {
+                let input = InputData { objective, game_data: game_data.clone(), ..alliance(8) };
+                let (queued, rescanned) = greedy_both_ways(&input);
+                assert!(!queued.is_empty());
+                assert_eq!(queued, rescanned, "{:?}, bonus {}", objective, game_data.is_some());
+            }
+        }
+    }
+
+    /// `cargo test --release bench_greedy -- --ignored --nocapture` times
+    /// both greedy loops on a 100 member alliance.
+    #[test]
+    #[ignore]
+    fn bench_greedy_alliance() {
+        for objective in [Objective::Linear, Objective::MaxMin] {
+            let input = InputData { objective, ..alliance(100) };
+            let game_data = input.game_data();
+            let (mut items, budget) = model::prepare_items(&input);
+            let (_, budget) = greedy::raise_to_floors(game_data, &mut items, budget);
+            let mut reference = items.clone();
+
+            let started = std::time::Instant::now();
+            let queued = greedy::run_greedy(game_data, objective, &mut items, budget);
+            let queue_time = started.elapsed();
+            let started = std::time::Instant::now();
+            let rescanned = greedy::run_greedy_rescan(game_data, objective, &mut reference, budget);
+            let rescan_time = started.elapsed();
+
+            assert_eq!(queued, rescanned);
+            eprintln!(
+                "{:?}: {} upgrades, queue {:.1} ms, rescan {:.1} ms",
+                objective,
+                queued.len(),
+                queue_time.as_secs_f64() * 1000.0,
+                rescan_time.as_secs_f64() * 1000.0
+            );
+        }
+    }
 }
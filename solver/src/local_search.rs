@@ -0,0 +1,110 @@
+use crate::greedy::{raise_to_floors, run_greedy};
+use crate::model::*;
+use crate::plan::*;
+use crate::types::*;
+
+/// Rounds of improvement before the search stops even if moves remain.
+const MAX_ROUNDS: usize = 50;
+
+/// Lower states worth trying for an upgraded piece: one level less, just
+/// below the last gate it crossed, and one mastery less (dropping any levels
+/// that mastery no longer allows).
+fn reductions(game_data: &GameData, start: &OptimizationItem, item: &OptimizationItem) -> Vec<(i32, i32)> {
+    let game_data = start.tables(game_data);
+    let mut out = Vec::new();
+    let (e, m) = (item.current_enhancement, item.mastery);
+
+    if e > start.current_enhancement {
+        out.push((e - 1, m));
+        let last_gate = ((start.current_enhancement + 1)..=e)
+            .rev()
+            .find(|&lvl| game_data.mythic_cost(lvl) > 0 || game_data.mythril_cost(lvl) > 0);
+        if let Some(gate) = last_gate.filter(|&gate| gate < e) {
+            out.push((gate - 1, m));
+        }
+    }
+
+    if m > start.mastery {
+        let mut lowered = e;
+        while lowered > start.current_enhancement && game_data.required_mastery(lowered) > m - 1 {
+            lowered -= 1;
+        }
+        out.push((lowered, m - 1));
+    }
+
+    out
+}
+
+/// What is left of `budget` after taking every item from `start` to `items`.
+fn remaining_budget(
+    game_data: &GameData,
+    start: &[OptimizationItem],
+    items: &[OptimizationItem],
+    budget: Resources,
+) -> Option<Resources> {
+    let mut remaining = budget;
+    for (from, to) in start.iter().zip(items) {
+        remaining -= upgrade_cost(from.tables(game_data), from.current_enhancement, from.mastery, to.current_enhancement, to.mastery)?;
+    }
+    remaining.is_non_negative().then_some(remaining)
+}
+
+/// Starts from the greedy allocation and repeatedly undoes part of one
+/// piece's upgrades, hands the freed resources to the other pieces with the
+/// greedy loop, then lets every piece spend what is still left. A move is
+/// kept whenever it raises the objective value.
+pub fn solve_local_search(input: InputData) -> OptimizationOutput {
+    let game_data = input.game_data();
+    let objective = input.objective;
+    let (start, budget) = prepare_items(&input);
+    let mut floors = start.clone();
+    let (floor_upgrades, budget) = raise_to_floors(game_data, &mut floors, budget);
+    let mut current = floors.clone();
+    run_greedy(game_data, objective, &mut current, budget);
+    let mut score = total_score(objective, game_data, &current);
+
+    for _ in 0..MAX_ROUNDS {
+        let mut improved = false;
+
+        for i in 0..current.len() {
+            for (enhancement, mastery) in reductions(game_data, &floors[i], &current[i]) {
+                let mut candidate = current.clone();
+                candidate[i].current_enhancement = enhancement;
+                candidate[i].mastery = mastery;
+                let Some(freed) = remaining_budget(game_data, &floors, &candidate, budget) else {
+                    continue;
+                };
+
+                // The reduced piece sits out the first pass, keeping the
+                // items laid out by hero for the objective.
+                let caps = (candidate[i].max_enhancement, candidate[i].max_mastery);
+                candidate[i].max_enhancement = enhancement;
+                candidate[i].max_mastery = mastery;
+                run_greedy(game_data, objective, &mut candidate, freed);
+                (candidate[i].max_enhancement, candidate[i].max_mastery) = caps;
+                let Some(left) = remaining_budget(game_data, &floors, &candidate, budget) else {
+                    continue;
+                };
+                run_greedy(game_data, objective, &mut candidate, left);
+
+                let candidate_score = total_score(objective, game_data, &candidate);
+                if candidate_score > score + 1e-9 * score.abs().max(1.0) {
+                    current = candidate;
+                    score = candidate_score;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+
+        if !improved {
+            break;
+        }
+    }
+
+    let mut output = build_output(&input, &start, &current);
+    let mut upgrades = floor_upgrades;
+    upgrades.extend(upgrades_between(game_data, &floors, &current));
+    output.plan = build_plan(&input, &start, &upgrades);
+    output
+}
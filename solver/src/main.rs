@@ -0,0 +1,755 @@
+//! Command-line front end for the gear optimizer.
+//!
+//! Reads `InputData` JSON from a file or stdin and prints the upgrade plan as
+//! a table, or as the same `{"ok": ...}` / `{"error": ...}` JSON the web app
+//! gets from `solve`.
+
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+use solver::*;
+
+const USAGE: &str = "\
+Usage: solver [OPTIONS] [INPUT]
+       solver [--game-data FILE] [--format FORMAT] --cost FROM TO
+
+Optimizes hero gear upgrades for the InputData JSON in INPUT, or stdin when
+INPUT is missing or `-`. With --batch, INPUT is a BatchInput with one
+InputData per alliance player.
+
+Options:
+  --strategy <NAME>     greedy, exact or local-search (overrides the input)
+  --objective <NAME>    linear, multiplicative, log-utility or max-min
+                        (overrides the input)
+  --game-data <FILE>    game tables to use instead of the built-in ones
+  --format <FORMAT>     table (default) or json
+  --batch               solve a whole alliance; the options apply to every
+                        player
+  --verify <FILE>       check the final gear in FILE, a list with one gear
+                        object per hero, instead of solving
+  --cost <FROM> <TO>    price one piece from FROM to TO, each written as
+                        enhancement/mastery (e.g. 118/10 140/12)
+  -h, --help            print this help
+
+Exit status is 0 on success, 1 when the input is rejected or cannot be
+solved, 2 for bad arguments and 3 when an input file cannot be read.";
+
+#[derive(PartialEq)]
+enum Format {
+    Table,
+    Json,
+}
+
+struct Args {
+    input: Option<String>,
+    strategy: Option<Strategy>,
+    objective: Option<Objective>,
+    game_data: Option<String>,
+    format: Format,
+    batch: bool,
+    verify: Option<String>,
+    cost: Option<(Gear, Gear)>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut parsed = Args {
+        input: None,
+        strategy: None,
+        objective: None,
+        game_data: None,
+        format: Format::Table,
+        batch: false,
+        verify: None,
+        cost: None,
+    };
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} needs a value", name))
+        };
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--strategy" => {
+                let name = value("--strategy")?;
+                let strategy = serde_json::from_value(serde_json::Value::String(name.clone()))
+                    .map_err(|_| format!("unknown strategy `{}`", name))?;
+                parsed.strategy = Some(strategy);
+            }
+            "--objective" => {
+                let name = value("--objective")?;
+                let objective = serde_json::from_value(serde_json::Value::String(name.clone()))
+                    .map_err(|_| format!("unknown objective `{}`", name))?;
+                parsed.objective = Some(objective);
+            }
+            "--game-data" => parsed.game_data = Some(value("--game-data")?),
+            "--batch" => parsed.batch = true,
+            "--verify" => parsed.verify = Some(value("--verify")?),
+            "--cost" => {
+                let from = parse_state(&value("--cost")?)?;
+                let to = parse_state(&args.next().ok_or("--cost needs a target state")?)?;
+                parsed.cost = Some((from, to));
+            }
+            "--format" => {
+                parsed.format = match value("--format")?.as_str() {
+                    "table" => Format::Table,
+                    "json" => Format::Json,
+                    other => return Err(format!("unknown format `{}`", other)),
+                }
+            }
+            "-" => parsed.input = None,
+            _ if flag.starts_with('-') => return Err(format!("unknown option `{}`", flag)),
+            _ if parsed.input.is_some() => return Err(format!("unexpected argument `{}`", arg)),
+            _ => parsed.input = Some(arg),
+        }
+    }
+    Ok(Some(parsed))
+}
+
+/// Reads `enhancement/mastery`, e.g. `118/10`.
+fn parse_state(text: &str) -> Result<Gear, String> {
+    let invalid = || format!("`{}` is not enhancement/mastery", text);
+    let (enhancement, mastery) = text.split_once('/').ok_or_else(invalid)?;
+    Ok(Gear {
+        enhancement: enhancement.parse().map_err(|_| invalid())?,
+        mastery: mastery.parse().map_err(|_| invalid())?,
+        ..Gear::default()
+    })
+}
+
+/// The files named on the command line, read before anything is solved so
+/// that a missing file is told apart from input the solver rejects.
+struct Files {
+    input: Option<String>,
+    game_data: Option<String>,
+    verify: Option<String>,
+}
+
+fn read_files(args: &Args) -> Result<Files, SolverError> {
+    let read = |path: &str| {
+        std::fs::read_to_string(path)
+            .map_err(|e| SolverError::parse(None, format!("cannot read {}: {}", path, e)))
+    };
+    let input = match (&args.cost, args.input.as_deref()) {
+        (Some(_), _) => None,
+        (None, Some(path)) => Some(read(path)?),
+        (None, None) => {
+            let mut data = String::new();
+            std::io::stdin()
+                .read_to_string(&mut data)
+                .map_err(|e| SolverError::parse(None, format!("cannot read stdin: {}", e)))?;
+            Some(data)
+        }
+    };
+    Ok(Files {
+        input,
+        game_data: args.game_data.as_deref().map(read).transpose()?,
+        verify: args.verify.as_deref().map(read).transpose()?,
+    })
+}
+
+fn load_game_data(files: &Files) -> Result<Option<GameData>, SolverError> {
+    let Some(data) = &files.game_data else {
+        return Ok(None);
+    };
+    parse_json(data).map(Some).map_err(|e| e.within("gameData"))
+}
+
+/// Applies the command-line overrides to `input`.
+fn apply_args(args: &Args, game_data: Option<&GameData>, input: &mut InputData) {
+    if let Some(strategy) = args.strategy {
+        input.strategy = strategy;
+    }
+    if let Some(objective) = args.objective {
+        input.objective = objective;
+    }
+    if let Some(game_data) = game_data {
+        input.game_data = Some(game_data.clone());
+    }
+}
+
+fn run(args: &Args, files: &Files) -> Result<OptimizationOutput, SolverError> {
+    let mut input = parse_input(files.input.as_deref().unwrap_or_default())?;
+    apply_args(args, load_game_data(files)?.as_ref(), &mut input);
+    solve_input(input)
+}
+
+fn run_verify(args: &Args, files: &Files) -> Result<PlanCheck, SolverError> {
+    let mut input = parse_input(files.input.as_deref().unwrap_or_default())?;
+    apply_args(args, load_game_data(files)?.as_ref(), &mut input);
+    let gear: Vec<HeroGear> = parse_json(files.verify.as_deref().unwrap_or_default())?;
+    verify_plan(&input, &gear)
+}
+
+fn run_cost(files: &Files, from: &Gear, to: &Gear) -> Result<TransitionCost, SolverError> {
+    match load_game_data(files)? {
+        Some(game_data) => {
+            game_data.validate().map_err(|e| e.within("gameData"))?;
+            cost_between(&game_data, from, to)
+        }
+        None => cost_between(GameData::builtin(), from, to),
+    }
+}
+
+fn run_batch(args: &Args, files: &Files) -> Result<BatchOutput, SolverError> {
+    let mut batch: BatchInput = parse_json(files.input.as_deref().unwrap_or_default())?;
+    let game_data = load_game_data(files)?;
+    for player in &mut batch.players {
+        apply_args(args, game_data.as_ref(), &mut player.input);
+    }
+    Ok(solve_batch_input(batch))
+}
+
+fn resources(r: &Resources) -> String {
+    format!(
+        "{} exp, {} hammers, {} mythics, {} mythril",
+        r.exp, r.hammers, r.mythics, r.mythril
+    )
+}
+
+fn print_table(out: &mut dyn Write, output: &OptimizationOutput) -> io::Result<()> {
+    for warning in &output.warnings {
+        writeln!(out, "warning: {}: {}", warning.field, warning.message)?;
+    }
+
+    if let Some(shop) = &output.shop {
+        for purchase in &shop.purchases {
+            writeln!(
+                out,
+                "buy:       {} x{} for {} gems ({})",
+                purchase.name,
+                purchase.quantity,
+                purchase.gems,
+                resources(&purchase.contents)
+            )?;
+        }
+        writeln!(
+            out,
+            "gems:      {} spent, {} left",
+            shop.gems_spent, shop.gems_leftover
+        )?;
+        writeln!(out)?;
+    }
+
+    writeln!(
+        out,
+        "{:<16} {:<12} {:>11} {:>10} {:>8} {:>8} {:>8} {:>8}  note",
+        "hero", "piece", "enhancement", "mastery", "exp", "hammers", "mythics", "mythril"
+    )?;
+    for result in &output.results {
+        for gear in &result.gear {
+            writeln!(
+                out,
+                "{:<16} {:<12} {:>4} -> {:<3} {:>3} -> {:<3} {:>8} {:>8} {:>8} {:>8}  {}",
+                result.hero_name,
+                gear.gear_type,
+                gear.current_enhancement,
+                gear.recommended_enhancement,
+                gear.current_mastery,
+                gear.recommended_mastery,
+                gear.spent.exp,
+                gear.spent.hammers,
+                gear.spent.mythics,
+                gear.spent.mythril,
+                match (&gear.crafted, gear.reset) {
+                    (Some(tier), _) => format!("crafted {}", tier),
+                    (None, true) => "reset".to_string(),
+                    (None, false) => String::new(),
+                },
+            )?;
+        }
+        if !result.set_bonuses.is_empty() {
+            writeln!(
+                out,
+                "{:<16} set bonuses: {}",
+                result.hero_name,
+                result.set_bonuses.join(", ")
+            )?;
+        }
+    }
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{:<4} {:<8} {:<16} {:<12} {:>9}  cost",
+        "step", "action", "hero", "piece", "levels"
+    )?;
+    for (i, step) in output.plan.iter().enumerate() {
+        let action = match step.action {
+            PlanAction::Reset => "reset",
+            PlanAction::Mastery => "mastery",
+            PlanAction::Enhance => "enhance",
+            PlanAction::Craft => "craft",
+        };
+        let cost = if step.action == PlanAction::Reset {
+            format!("refund {} exp", step.refund.exp)
+        } else {
+            resources(&step.cost)
+        };
+        writeln!(
+            out,
+            "{:<4} {:<8} {:<16} {:<12} {:>4}->{:<4}  {}",
+            i + 1,
+            action,
+            step.hero_name,
+            step.gear_type,
+            step.from,
+            step.to,
+            cost
+        )?;
+    }
+
+    if !output.crafting.is_empty() {
+        writeln!(out)?;
+        for option in &output.crafting {
+            writeln!(
+                out,
+                "craft:     {} {} {} -> {}: score {:.4} vs {:.4} enhancing, {}{}",
+                option.hero_name,
+                option.gear_type,
+                option.from_tier.as_deref().unwrap_or("top"),
+                option.to_tier,
+                option.craft_score,
+                option.enhance_score,
+                resources(&option.cost),
+                if option.chosen { " (chosen)" } else { "" }
+            )?;
+        }
+    }
+
+    if let Some(exp_items) = &output.exp_items {
+        writeln!(out)?;
+        for feed in &exp_items.feeds {
+            let items: Vec<String> = feed
+                .items
+                .iter()
+                .map(|s| format!("{}x{}", s.count, s.exp))
+                .collect();
+            writeln!(
+                out,
+                "feed:      {} {}: {} + {} loose exp ({} wasted)",
+                feed.hero_name,
+                feed.gear_type,
+                if items.is_empty() {
+                    "-".to_string()
+                } else {
+                    items.join(" ")
+                },
+                feed.loose_exp,
+                feed.wasted_exp
+            )?;
+        }
+    }
+
+    for period in &output.schedule {
+        let snapshot = &period.output;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "period {}:  {} steps, score {:.4} -> {:.4}, leftover {}",
+            period.period,
+            snapshot.plan.len(),
+            snapshot.total_before_score,
+            snapshot.total_after_score,
+            resources(&snapshot.leftover)
+        )?;
+        for step in &period.waiting_for {
+            writeln!(
+                out,
+                "  saving:  {} {} {}->{}, needs {}",
+                step.hero_name,
+                step.gear_type,
+                step.from,
+                step.to,
+                resources(&step.remaining)
+            )?;
+        }
+    }
+
+    if !output.bottlenecks.is_empty() {
+        writeln!(out)?;
+        for bottleneck in &output.bottlenecks {
+            writeln!(
+                out,
+                "blocked:   {} {}: {}",
+                bottleneck.hero_name, bottleneck.gear_type, bottleneck.message
+            )?;
+        }
+        let ranking: Vec<String> = output
+            .resource_ranking
+            .iter()
+            .map(|r| {
+                format!(
+                    "{} ({} steps, {} missing)",
+                    r.resource, r.blocked, r.missing
+                )
+            })
+            .collect();
+        if !ranking.is_empty() {
+            writeln!(out, "short of:  {}", ranking.join(", "))?;
+        }
+    }
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "score:     {:.4} -> {:.4}",
+        output.total_before_score, output.total_after_score
+    )?;
+    if let Some(gap) = &output.greedy_gap {
+        let proof = if gap.proven_optimal {
+            "proven optimal"
+        } else {
+            "not proven optimal"
+        };
+        writeln!(
+            out,
+            "greedy:    {:.4} ({:.2}% below, {})",
+            gap.greedy_score,
+            gap.relative_gap * 100.0,
+            proof
+        )?;
+    }
+    if let Some(prices) = &output.shadow_prices {
+        writeln!(
+            out,
+            "per unit:  {:.3e} exp, {:.3e} hammers, {:.3e} mythics, {:.3e} mythril",
+            prices.exp, prices.hammers, prices.mythics, prices.mythril
+        )?;
+        for bundle in &prices.bundles {
+            writeln!(
+                out,
+                "bundle:    {} +{:.4} ({})",
+                bundle.name,
+                bundle.score_gain,
+                resources(&bundle.contents)
+            )?;
+        }
+    }
+    for point in &output.pareto {
+        writeln!(
+            out,
+            "frontier:  lethality {:.4}, health {:.4} (weights {:.3}/{:.3})",
+            point.lethality, point.health, point.weights.lethality, point.weights.health
+        )?;
+    }
+    if let Some(goal) = &output.goal {
+        writeln!(out, "required:  {}", resources(&goal.required))?;
+        writeln!(out, "missing:   {}", resources(&goal.shortfall))?;
+    }
+    writeln!(out, "used:      {}", resources(&output.used))?;
+    writeln!(out, "leftover:  {}", resources(&output.leftover))?;
+    writeln!(out, "reclaimed: {} exp", output.reclaimed_exp)?;
+    writeln!(
+        out,
+        "strategy:  {} in {:.1} ms, game data {}",
+        serde_json::to_value(output.strategy)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default(),
+        output.runtime_ms,
+        output.game_data_version
+    )?;
+    Ok(())
+}
+
+fn print_batch(out: &mut dyn Write, output: &BatchOutput) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:<20} {:>10} {:>10}  note",
+        "player", "before", "after"
+    )?;
+    for player in &output.players {
+        match &player.result {
+            SolveResult::Ok(solved) => writeln!(
+                out,
+                "{:<20} {:>10.4} {:>10.4}",
+                player.name, solved.total_before_score, solved.total_after_score
+            )?,
+            SolveResult::Error(error) => writeln!(
+                out,
+                "{:<20} {:>10} {:>10}  {}",
+                player.name, "-", "-", error
+            )?,
+        }
+    }
+
+    let summary = &output.summary;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{:<9} {:>9} {:>11}",
+        "levels", "current", "recommended"
+    )?;
+    for bucket in &summary.enhancement_levels {
+        writeln!(
+            out,
+            "{:>3}-{:<5} {:>9} {:>11}",
+            bucket.from, bucket.to, bucket.current, bucket.recommended
+        )?;
+    }
+    writeln!(out)?;
+    for block in &summary.mythic_blocked {
+        writeln!(
+            out,
+            "mythics:   {} would gain {:.4}",
+            block.name, block.score_gain
+        )?;
+    }
+    writeln!(
+        out,
+        "score:     {:.4} -> {:.4} (+{:.4})",
+        summary.total_before_score, summary.total_after_score, summary.total_score_gain
+    )?;
+    writeln!(
+        out,
+        "players:   {} solved, {} rejected",
+        summary.solved,
+        summary.failed.len()
+    )?;
+    Ok(())
+}
+
+fn print_check(out: &mut dyn Write, check: &PlanCheck) -> io::Result<()> {
+    for violation in &check.violations {
+        writeln!(out, "violation: {}: {}", violation.field, violation.message)?;
+    }
+    writeln!(
+        out,
+        "feasible:  {}",
+        if check.feasible { "yes" } else { "no" }
+    )?;
+    writeln!(out, "required:  {}", resources(&check.required))?;
+    writeln!(out, "reclaimed: {} exp", check.reclaimed_exp)?;
+    writeln!(out, "missing:   {}", resources(&check.shortfall))?;
+    Ok(())
+}
+
+fn print_cost(out: &mut dyn Write, cost: &TransitionCost) -> io::Result<()> {
+    for gate in &cost.gates {
+        writeln!(
+            out,
+            "gate:      {} ({} mythics, {} mythril, mastery {})",
+            gate.level, gate.mythics, gate.mythril, gate.required_mastery
+        )?;
+    }
+    writeln!(out, "mastery:   {}", cost.mastery)?;
+    writeln!(out, "cost:      {}", resources(&cost.cost))?;
+    Ok(())
+}
+
+type Printer<T> = fn(&mut dyn Write, &T) -> io::Result<()>;
+
+/// Prints `result` in the chosen format and returns whether it failed. A
+/// closed pipe (e.g. `| head`) just ends the output.
+fn report<T: serde::Serialize>(
+    args: &Args,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+    result: Result<T, SolverError>,
+    print: Printer<T>,
+) -> bool {
+    let _ = match result {
+        Ok(output) if args.format == Format::Json => {
+            writeln!(out, "{}", json(&SolveResult::from(Ok(output))))
+        }
+        Ok(output) => print(out, &output),
+        Err(error) => {
+            report_error(args, out, err, error);
+            return true;
+        }
+    };
+    false
+}
+
+fn report_error(args: &Args, out: &mut dyn Write, err: &mut dyn Write, error: SolverError) {
+    let _ = if args.format == Format::Json {
+        writeln!(out, "{}", json(&SolveResult::<()>::Error(error)))
+    } else {
+        writeln!(err, "{:?} error: {}", error.kind, error)
+    };
+}
+
+fn json<T: serde::Serialize>(result: &SolveResult<T>) -> String {
+    serde_json::to_string_pretty(result).expect("results always serialize")
+}
+
+/// Runs the command line in `argv` and returns the exit status: 0 on
+/// success, 1 when the solver rejects or fails on the input, 2 for bad
+/// arguments and 3 when an input file cannot be read.
+fn cli(argv: impl Iterator<Item = String>, out: &mut dyn Write, err: &mut dyn Write) -> u8 {
+    let args = match parse_args(argv) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            let _ = writeln!(out, "{}", USAGE);
+            return 0;
+        }
+        Err(message) => {
+            let _ = writeln!(err, "error: {}\n\n{}", message, USAGE);
+            return 2;
+        }
+    };
+    let files = match read_files(&args) {
+        Ok(files) => files,
+        Err(error) => {
+            report_error(&args, out, err, error);
+            return 3;
+        }
+    };
+
+    let failed = if let Some((from, to)) = &args.cost {
+        report(&args, out, err, run_cost(&files, from, to), print_cost)
+    } else if args.verify.is_some() {
+        report(&args, out, err, run_verify(&args, &files), print_check)
+    } else if args.batch {
+        report(&args, out, err, run_batch(&args, &files), print_batch)
+    } else {
+        report(&args, out, err, run(&args, &files), print_table)
+    };
+    u8::from(failed)
+}
+
+fn main() -> ExitCode {
+    let status = cli(
+        std::env::args().skip(1),
+        &mut std::io::stdout().lock(),
+        &mut std::io::stderr().lock(),
+    );
+    ExitCode::from(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(argv: &[&str]) -> (u8, String, String) {
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        let status = cli(argv.iter().map(|arg| arg.to_string()), &mut out, &mut err);
+        (
+            status,
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(err).unwrap(),
+        )
+    }
+
+    fn input_file(name: &str, input: &InputData) -> String {
+        let path =
+            std::env::temp_dir().join(format!("solver-cli-{}-{}.json", std::process::id(), name));
+        std::fs::write(&path, serde_json::to_string(input).unwrap()).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    /// A pipe whose reader has gone away.
+    struct Closed;
+
+    impl Write for Closed {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parse = |argv: &[&str]| parse_args(argv.iter().map(|arg| arg.to_string()));
+        let args = parse(&["--strategy=exact", "--format", "json", "--batch", "in.json"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(args.input.as_deref(), Some("in.json"));
+        assert_eq!(args.strategy, Some(Strategy::Exact));
+        assert!(args.format == Format::Json && args.batch);
+
+        let args = parse(&["--cost", "118/10", "140/12"]).unwrap().unwrap();
+        let (from, to) = args.cost.unwrap();
+        assert_eq!(
+            (from.enhancement, from.mastery, to.enhancement, to.mastery),
+            (118, 10, 140, 12)
+        );
+
+        assert!(parse(&["--help"]).unwrap().is_none());
+        assert_eq!(
+            parse(&["--format", "xml"]).err().unwrap(),
+            "unknown format `xml`"
+        );
+        assert_eq!(
+            parse(&["--strategy"]).err().unwrap(),
+            "--strategy needs a value"
+        );
+        assert_eq!(
+            parse(&["--cost", "118"]).err().unwrap(),
+            "`118` is not enhancement/mastery"
+        );
+        assert_eq!(
+            parse(&["a.json", "b.json"]).err().unwrap(),
+            "unexpected argument `b.json`"
+        );
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let hero = HeroWeights {
+            name: "Cli".to_string(),
+            gear: HeroGear::default(),
+            weights: StatWeights {
+                lethality: 1.0,
+                health: 1.0,
+            },
+            set_bonuses: Vec::new(),
+        };
+        let valid = input_file(
+            "valid",
+            &InputData {
+                heroes: vec![hero],
+                exp: 10_000,
+                ..Default::default()
+            },
+        );
+        let rejected = input_file(
+            "rejected",
+            &InputData {
+                exp: -1,
+                ..Default::default()
+            },
+        );
+        let missing = std::env::temp_dir()
+            .join("solver-cli-missing.json")
+            .to_string_lossy()
+            .into_owned();
+
+        let (status, out, _) = call(&[&valid]);
+        assert_eq!(status, 0);
+        assert!(out.contains("score:"));
+        let (status, out, _) = call(&["--help"]);
+        assert_eq!((status, out.starts_with("Usage")), (0, true));
+        let (status, _, err) = call(&[&rejected]);
+        assert_eq!(status, 1);
+        assert!(err.starts_with("Validation error"));
+        let (status, _, err) = call(&["--bogus"]);
+        assert_eq!(status, 2);
+        assert!(err.starts_with("error: unknown option `--bogus`"));
+        let (status, _, err) = call(&[&missing]);
+        assert_eq!(status, 3);
+        assert!(err.starts_with("Parse error: cannot read"));
+        let (status, out, _) = call(&["--format", "json", "--game-data", &missing, &valid]);
+        assert_eq!(status, 3);
+        assert!(out.contains("\"kind\": \"parse\""));
+
+        // A closed pipe cuts the output short without failing the run.
+        let args = ["--format", "table", valid.as_str()].map(str::to_string);
+        assert_eq!(cli(args.into_iter(), &mut Closed, &mut Vec::new()), 0);
+
+        for path in [valid, rejected] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
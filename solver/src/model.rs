@@ -0,0 +1,283 @@
+use std::rc::Rc;
+
+use crate::reset::reset_decisions;
+use crate::types::*;
+
+/// A single gear piece flattened out of `InputData`. Pieces are laid out hero
+/// by hero in the slot order of the game data's gear set, so hero `h` owns
+/// items `h * slots..(h + 1) * slots`.
+#[derive(Clone)]
+pub struct OptimizationItem {
+    pub is_lethality: bool,
+    pub mastery: i32,
+    pub weights_lethality: f64,
+    pub weights_health: f64,
+    pub current_enhancement: i32,
+    /// Enhancement before any reset, as given in the input.
+    pub original_enhancement: i32,
+    /// EXP the reset of this item gives back, 0 when it is not reset.
+    pub refund: i32,
+    /// Bounds from the per-piece constraints. Solvers must end every item
+    /// within them; `raise_to_floors` takes care of the lower ones.
+    pub min_enhancement: i32,
+    pub max_enhancement: i32,
+    pub min_mastery: i32,
+    pub max_mastery: i32,
+    /// The hero's own set bonuses, shared by all their pieces.
+    pub set_bonuses: Rc<[SetBonus]>,
+    /// Rank of the piece's gear tier.
+    pub tier: usize,
+}
+
+impl OptimizationItem {
+    /// The tables of this piece's tier.
+    pub fn tables<'a>(&self, game_data: &'a GameData) -> &'a GameData {
+        game_data.tables(self.tier)
+    }
+
+    pub fn level(&self, enhancement: i32, mastery: i32) -> PieceLevel {
+        PieceLevel { tier: self.tier, enhancement, mastery }
+    }
+
+    pub fn score(&self, game_data: &GameData, enhancement: i32, mastery: i32) -> f64 {
+        let s = self.tables(game_data).stat(enhancement, mastery);
+        if self.is_lethality {
+            s * self.weights_lethality
+        } else {
+            s * self.weights_health
+        }
+    }
+}
+
+/// Flattens the heroes into optimization items and returns the resources
+/// available to the solver, resetting the pieces the input's reset policy
+/// picks.
+pub fn prepare_items(input: &InputData) -> (Vec<OptimizationItem>, Resources) {
+    prepare_items_with(input, &reset_decisions(input))
+}
+
+/// Whether a piece may be reset at all.
+pub fn can_reset(gear: &Gear) -> bool {
+    !gear.locked && !gear.no_reset && gear.enhancement > 0
+}
+
+/// Like `prepare_items`, with the reset decision for every piece given in
+/// item order. Reset pieces go back to 0 and the policy's refund is added to
+/// the EXP pool.
+pub fn prepare_items_with(input: &InputData, resets: &[bool]) -> (Vec<OptimizationItem>, Resources) {
+    let game_data = input.game_data();
+    let mut budget = input.budget();
+    let mut items = Vec::with_capacity(input.heroes.len() * game_data.slot_count());
+
+    for hero in &input.heroes {
+        let set_bonuses: Rc<[SetBonus]> = hero.set_bonuses.clone().into();
+        for (slot, gear) in game_data.gear_set.slots.iter().zip(game_data.pieces(&hero.gear)) {
+            let tier = game_data.tier_rank(gear.tier.as_deref()).unwrap_or(game_data.top_tier());
+            let tables = game_data.tables(tier);
+            let (start_enhancement, refund) = if resets[items.len()] {
+                (0, input.reset_policy.refund(tables, gear.enhancement))
+            } else {
+                (gear.enhancement, 0)
+            };
+            budget.exp = budget.exp.saturating_add(refund);
+
+            let max_enhancement = tables.max_enhancement;
+            let (min_enhancement, max_enhancement, min_mastery, max_mastery) = if gear.locked {
+                (start_enhancement, start_enhancement, gear.mastery, gear.mastery)
+            } else {
+                let max_enhancement = gear
+                    .max_enhancement
+                    .map_or(max_enhancement, |cap| cap.min(max_enhancement))
+                    .max(start_enhancement);
+                let min_enhancement = gear.min_enhancement.unwrap_or(0).clamp(start_enhancement, max_enhancement);
+                let max_mastery = gear.target_mastery.unwrap_or(tables.max_mastery).max(gear.mastery);
+                let min_mastery = gear.target_mastery.unwrap_or(0).clamp(gear.mastery, max_mastery);
+                (min_enhancement, max_enhancement, min_mastery, max_mastery)
+            };
+
+            items.push(OptimizationItem {
+                is_lethality: slot.stat == SlotStat::Lethality,
+                mastery: gear.mastery,
+                weights_lethality: hero.weights.lethality,
+                weights_health: hero.weights.health,
+                current_enhancement: start_enhancement,
+                original_enhancement: gear.enhancement,
+                refund,
+                min_enhancement,
+                max_enhancement,
+                min_mastery,
+                max_mastery,
+                set_bonuses: set_bonuses.clone(),
+                tier,
+            });
+        }
+    }
+
+    (items, budget)
+}
+
+/// Whether any hero of `items` can earn a set bonus, in which case a piece's
+/// gain depends on the other pieces of its hero.
+pub fn has_set_bonuses(game_data: &GameData, items: &[OptimizationItem]) -> bool {
+    !game_data.gear_set.bonuses.is_empty() || items.iter().any(|item| !item.set_bonuses.is_empty())
+}
+
+/// EXP, mythics and mythril needed to enhance a piece from `from` to `to`.
+pub fn enhancement_cost(game_data: &GameData, from: i32, to: i32) -> Resources {
+    let mut cost = Resources {
+        exp: game_data.exp_cost(to) - game_data.exp_cost(from),
+        ..Resources::default()
+    };
+    for lvl in (from + 1)..=to {
+        cost.mythril += game_data.mythril_cost(lvl);
+        cost.mythics += game_data.mythic_cost(lvl);
+    }
+    cost
+}
+
+/// Hammers and mythics needed to raise mastery from `from` to `to`.
+pub fn mastery_cost(game_data: &GameData, from: i32, to: i32) -> Resources {
+    let mut cost = Resources::default();
+    for m in (from + 1)..=to {
+        cost.hammers += game_data.hammer_cost(m);
+        cost.mythics += game_data.mastery_mythic_cost(m);
+    }
+    cost
+}
+
+/// Resources needed to take a piece from `(from_enhancement, from_mastery)` to
+/// `(to_enhancement, to_mastery)`, or `None` if the target breaks a mastery
+/// prerequisite for one of the new enhancement levels.
+pub fn upgrade_cost(
+    game_data: &GameData,
+    from_enhancement: i32,
+    from_mastery: i32,
+    to_enhancement: i32,
+    to_mastery: i32,
+) -> Option<Resources> {
+    if to_enhancement > from_enhancement && to_mastery < game_data.required_mastery(to_enhancement) {
+        return None;
+    }
+    Some(
+        enhancement_cost(game_data, from_enhancement, to_enhancement)
+            + mastery_cost(game_data, from_mastery, to_mastery),
+    )
+}
+
+/// Whether the reset of `start` is actually carried out. A reset piece that
+/// ends at or above its original level is left alone instead, since
+/// re-levelling it costs at least the EXP the reset gives back.
+pub fn keeps_reset(start: &OptimizationItem, final_enhancement: i32) -> bool {
+    start.current_enhancement < start.original_enhancement && final_enhancement < start.original_enhancement
+}
+
+/// Resources spent on a piece going from `start` to `item`, and the EXP its
+/// reset gave back.
+pub fn piece_spending(game_data: &GameData, start: &OptimizationItem, item: &OptimizationItem) -> (Resources, i32) {
+    let game_data = start.tables(game_data);
+    let mastery = mastery_cost(game_data, start.mastery, item.mastery);
+    if keeps_reset(start, item.current_enhancement) {
+        let reclaimed = start.refund;
+        let enhancement = enhancement_cost(game_data, start.current_enhancement, item.current_enhancement);
+        (enhancement + mastery, reclaimed)
+    } else {
+        let from = start.current_enhancement.max(start.original_enhancement);
+        (enhancement_cost(game_data, from, item.current_enhancement) + mastery, 0)
+    }
+}
+
+/// The objective value of the items, matching `total_after_score` of the
+/// output built from the same items.
+pub fn total_score(objective: Objective, game_data: &GameData, items: &[OptimizationItem]) -> f64 {
+    objective.value(game_data, items)
+}
+
+/// Builds the solver output from the original input, the items produced by
+/// `prepare_items` and their final state.
+pub fn build_output(
+    input: &InputData,
+    start: &[OptimizationItem],
+    items: &[OptimizationItem],
+) -> OptimizationOutput {
+    let game_data = input.game_data();
+    let mut new_heroes = input.heroes.clone();
+    let slots = game_data.slot_count();
+    for (hero, hero_items) in new_heroes.iter_mut().zip(items.chunks(slots)) {
+        for (gear, item) in game_data.pieces_mut(&mut hero.gear).into_iter().zip(hero_items) {
+            gear.enhancement = item.current_enhancement;
+            gear.mastery = item.mastery;
+        }
+    }
+
+    let objective = input.objective;
+    let mut results = Vec::new();
+    let mut used = Resources::default();
+    let mut reclaimed_exp: i32 = 0;
+
+    for ((before, after), (hero_start, hero_items)) in input
+        .heroes
+        .iter()
+        .zip(&new_heroes)
+        .zip(start.chunks(slots).zip(items.chunks(slots)))
+    {
+        let before_stats = game_data.calculate_stats(&before.gear, &before.set_bonuses);
+        let before_score = objective.hero_value(&before.weights, &before_stats);
+
+        let after_stats = game_data.calculate_stats(&after.gear, &after.set_bonuses);
+        let after_score = objective.hero_value(&after.weights, &after_stats);
+
+        let gear: Vec<GearResult> = game_data
+            .gear_set
+            .slots
+            .iter()
+            .zip(game_data.pieces(&before.gear))
+            .zip(game_data.pieces(&after.gear))
+            .zip(hero_start.iter().zip(hero_items))
+            .map(|(((slot, current), recommended), (item_start, item))| {
+                let (spent, reclaimed_exp) = piece_spending(game_data, item_start, item);
+                let reset = keeps_reset(item_start, item.current_enhancement);
+                GearResult {
+                    gear_type: slot.name.clone(),
+                    current_mastery: current.mastery,
+                    recommended_mastery: recommended.mastery,
+                    current_enhancement: current.enhancement,
+                    recommended_enhancement: recommended.enhancement,
+                    spent,
+                    reclaimed_exp,
+                    reset,
+                    crafted: None,
+                }
+            })
+            .collect();
+
+        let spent = gear.iter().fold(Resources::default(), |acc, g| acc + g.spent);
+        let hero_reclaimed_exp = gear.iter().fold(0, |total: i32, g| total.saturating_add(g.reclaimed_exp));
+        used += spent;
+        reclaimed_exp = reclaimed_exp.saturating_add(hero_reclaimed_exp);
+
+        results.push(OptimizationResult {
+            hero_name: before.name.clone(),
+            gear,
+            before_stats,
+            after_stats,
+            before_score,
+            after_score,
+            spent,
+            reclaimed_exp: hero_reclaimed_exp,
+            set_bonuses: game_data.earned_bonuses(&after.gear, &after.set_bonuses),
+        });
+    }
+
+    let budget = input.budget() + Resources { exp: reclaimed_exp, ..Resources::default() };
+
+    OptimizationOutput {
+        total_before_score: objective.combine(results.iter().map(|result| result.before_score)),
+        total_after_score: objective.combine(results.iter().map(|result| result.after_score)),
+        results,
+        used,
+        leftover: budget - used,
+        reclaimed_exp,
+        game_data_version: game_data.version.clone(),
+        ..OptimizationOutput::default()
+    }
+}
@@ -0,0 +1,223 @@
+use crate::model::{has_set_bonuses, OptimizationItem};
+use crate::types::*;
+
+/// How much of the roster's total score is added to the weakest hero's under
+/// `MaxMin`, so that of two allocations with the same weakest hero the
+/// stronger roster wins.
+const MAX_MIN_TIE_BREAK: f64 = 1e-4;
+
+impl Objective {
+    /// What one hero contributes. Under `MaxMin` this is the hero's weighted
+    /// score, and `combine` takes the lowest.
+    pub fn hero_value(self, weights: &StatWeights, stats: &Stats) -> f64 {
+        let lethality = stats.lethality * weights.lethality;
+        let health = stats.health * weights.health;
+        match self {
+            Objective::Linear | Objective::MaxMin => lethality + health,
+            Objective::Multiplicative => (1.0 + lethality) * (1.0 + health),
+            Objective::LogUtility => (1.0 + lethality).ln() + (1.0 + health).ln(),
+        }
+    }
+
+    /// The objective value of a roster from the values of its heroes.
+    pub fn combine(self, hero_values: impl IntoIterator<Item = f64>) -> f64 {
+        match self {
+            Objective::MaxMin => {
+                let (lowest, total) = hero_values
+                    .into_iter()
+                    .fold((f64::INFINITY, 0.0), |(lowest, total), value| (lowest.min(value), total + value));
+                if lowest.is_finite() {
+                    lowest + MAX_MIN_TIE_BREAK * total
+                } else {
+                    0.0
+                }
+            }
+            _ => hero_values.into_iter().sum(),
+        }
+    }
+
+    /// The objective value of `items`, laid out hero by hero.
+    pub fn value(self, game_data: &GameData, items: &[OptimizationItem]) -> f64 {
+        self.combine(items.chunks(game_data.slot_count()).map(|hero| {
+            let stats = hero_stats(game_data, hero, |item| (item.current_enhancement, item.mastery));
+            self.hero_value(&hero_weights(hero), &stats)
+        }))
+    }
+}
+
+fn hero_weights(hero: &[OptimizationItem]) -> StatWeights {
+    StatWeights { lethality: hero[0].weights_lethality, health: hero[0].weights_health }
+}
+
+/// Lethality and health of one hero's items, each taken at the state `at`
+/// picks, with the set bonuses they earn.
+fn hero_stats(game_data: &GameData, hero: &[OptimizationItem], at: impl Fn(&OptimizationItem) -> (i32, i32)) -> Stats {
+    let levels = hero.iter().map(|item| {
+        let (enhancement, mastery) = at(item);
+        item.level(enhancement, mastery)
+    });
+    game_data.stats_at(levels, &hero[0].set_bonuses)
+}
+
+/// Bounds on what each piece can add to the objective, whatever the other
+/// pieces do, so that the bounds of all pieces plus `offset` bound the gain
+/// of the whole roster. The exact search prunes with them. Under `Linear`
+/// without set bonuses they are the exact gains.
+pub struct GainBounds<'a> {
+    objective: Objective,
+    game_data: &'a GameData,
+    items: &'a [OptimizationItem],
+    floor: Vec<Stats>,
+    ceiling: Vec<Stats>,
+    /// Under `MaxMin`, the hero whose score bounds the lowest one.
+    bounded_hero: usize,
+    pub offset: f64,
+}
+
+impl<'a> GainBounds<'a> {
+    /// `items` are at their starting state, with `max_enhancement` and
+    /// `max_mastery` at most what they can reach.
+    pub fn new(objective: Objective, game_data: &'a GameData, items: &'a [OptimizationItem]) -> Self {
+        let slots = game_data.slot_count();
+        let floor_at = |item: &OptimizationItem| (item.current_enhancement, item.mastery);
+        let ceiling_at = |item: &OptimizationItem| (item.max_enhancement, item.max_mastery);
+        let floor: Vec<Stats> = items.chunks(slots).map(|hero| hero_stats(game_data, hero, floor_at)).collect();
+        let ceiling: Vec<Stats> = items.chunks(slots).map(|hero| hero_stats(game_data, hero, ceiling_at)).collect();
+        let linear = |h: usize, stats: &Stats| Objective::Linear.hero_value(&hero_weights(&items[h * slots..]), stats);
+        // The lowest score is at most any one hero's, so the hero with the
+        // lowest ceiling gives the tightest bound.
+        let bounded_hero = (0..ceiling.len())
+            .min_by(|&a, &b| linear(a, &ceiling[a]).total_cmp(&linear(b, &ceiling[b])))
+            .unwrap_or(0);
+        let offset = match objective {
+            Objective::MaxMin if !floor.is_empty() => {
+                let total: f64 = floor.iter().enumerate().map(|(h, stats)| linear(h, stats)).sum();
+                linear(bounded_hero, &floor[bounded_hero]) + MAX_MIN_TIE_BREAK * total - objective.value(game_data, items)
+            }
+            _ => 0.0,
+        };
+        let mut bounds = GainBounds { objective, game_data, items, floor, ceiling, bounded_hero, offset: offset.max(0.0) };
+        // Set bonuses belong to no single piece, so those a hero can still
+        // earn are bounded as a whole.
+        for (h, hero) in items.chunks(slots).enumerate() {
+            let floor_levels: Vec<PieceLevel> =
+                hero.iter().map(|item| item.level(item.current_enhancement, item.mastery)).collect();
+            let ceiling_levels: Vec<PieceLevel> =
+                hero.iter().map(|item| item.level(item.max_enhancement, item.max_mastery)).collect();
+            for bonus in game_data.gear_set.bonuses.iter().chain(hero[0].set_bonuses.iter()) {
+                if !game_data.earns(bonus, &floor_levels) && game_data.earns(bonus, &ceiling_levels) {
+                    bounds.offset += bounds.raise(h, true, bonus.bonus.lethality) + bounds.raise(h, false, bonus.bonus.health);
+                }
+            }
+        }
+        bounds
+    }
+
+    /// A bound on what taking item `i` to `(enhancement, mastery)` adds.
+    pub fn gain(&self, i: usize, enhancement: i32, mastery: i32) -> f64 {
+        let item = &self.items[i];
+        let tables = item.tables(self.game_data);
+        let delta = tables.stat(enhancement, mastery) - tables.stat(item.current_enhancement, item.mastery);
+        self.raise(i / self.game_data.slot_count(), item.is_lethality, delta)
+    }
+
+    /// A bound on what raising one of `hero`'s stats by `amount` adds.
+    fn raise(&self, hero: usize, lethality: bool, amount: f64) -> f64 {
+        let split = |stats: &Stats| if lethality { (stats.lethality, stats.health) } else { (stats.health, stats.lethality) };
+        let weights = hero_weights(&self.items[hero * self.game_data.slot_count()..]);
+        let (own, other) = split(&Stats { lethality: weights.lethality, health: weights.health });
+        match self.objective {
+            Objective::Linear => own * amount,
+            // With `dl` and `dh` the rises of the two stats, the product gains
+            // `dl * (1 + h) + dh * (1 + l) + dl * dh`, and `dl * dh` is at most
+            // half of `dl * max(dh) + dh * max(dl)`.
+            Objective::Multiplicative => {
+                let floor = split(&self.floor[hero]).1;
+                let ceiling = split(&self.ceiling[hero]).1;
+                own * amount * (1.0 + other * (floor + ceiling) / 2.0)
+            }
+            // The logarithm is steepest at the starting point.
+            Objective::LogUtility => own * amount / (1.0 + own * split(&self.floor[hero]).0),
+            Objective::MaxMin if hero == self.bounded_hero => own * amount * (1.0 + MAX_MIN_TIE_BREAK),
+            Objective::MaxMin => own * amount * MAX_MIN_TIE_BREAK,
+        }
+    }
+}
+
+/// Scores single-level moves for the greedy loop against the current state
+/// of every hero.
+pub struct Gains<'a> {
+    objective: Objective,
+    game_data: &'a GameData,
+    /// Whether each piece's gain can be scored on its own.
+    per_piece: bool,
+    values: Vec<f64>,
+    lowest: f64,
+}
+
+impl<'a> Gains<'a> {
+    pub fn new(objective: Objective, game_data: &'a GameData, items: &[OptimizationItem]) -> Self {
+        if objective == Objective::Linear && !has_set_bonuses(game_data, items) {
+            return Gains { objective, game_data, per_piece: true, values: Vec::new(), lowest: 0.0 };
+        }
+        let mut gains = Gains { objective, game_data, per_piece: false, values: Vec::new(), lowest: 0.0 };
+        gains.values = items.chunks(game_data.slot_count()).map(|hero| gains.hero_value(hero)).collect();
+        gains.lowest = gains.values.iter().copied().fold(f64::INFINITY, f64::min);
+        gains
+    }
+
+    fn hero_value(&self, hero: &[OptimizationItem]) -> f64 {
+        let stats = hero_stats(self.game_data, hero, |item| (item.current_enhancement, item.mastery));
+        self.objective.hero_value(&hero_weights(hero), &stats)
+    }
+
+    /// Whether the gains of `hero` only count towards the `MaxMin` tie-break.
+    fn above_lowest(&self, hero: usize) -> bool {
+        self.objective == Objective::MaxMin && self.values[hero] > self.lowest + 1e-12 * self.lowest.abs().max(1.0)
+    }
+
+    /// Catches up with `heroes`, whose items changed, and returns every hero
+    /// whose gains changed with them: under `MaxMin` also those a new lowest
+    /// score moves across the tie.
+    pub fn update(&mut self, items: &[OptimizationItem], heroes: &[usize]) -> Vec<usize> {
+        if self.per_piece {
+            return heroes.to_vec();
+        }
+        let slots = self.game_data.slot_count();
+        let before: Vec<bool> = (0..self.values.len()).map(|h| self.above_lowest(h)).collect();
+        for &hero in heroes {
+            self.values[hero] = self.hero_value(&items[hero * slots..(hero + 1) * slots]);
+        }
+        self.lowest = self.values.iter().copied().fold(f64::INFINITY, f64::min);
+        (0..self.values.len()).filter(|h| heroes.contains(h) || before[*h] != self.above_lowest(*h)).collect()
+    }
+
+    /// What moving item `i` of `items` to `(enhancement, mastery)` adds.
+    /// Under `MaxMin` a hero tied for the lowest score counts its whole
+    /// gain, since raising such heroes is what moves the minimum; the others
+    /// only count towards the tie-break.
+    pub fn gain(&self, items: &[OptimizationItem], i: usize, enhancement: i32, mastery: i32) -> f64 {
+        let item = &items[i];
+        if self.per_piece {
+            return item.score(self.game_data, enhancement, mastery)
+                - item.score(self.game_data, item.current_enhancement, item.mastery);
+        }
+        let slots = self.game_data.slot_count();
+        let hero = i / slots;
+        let first = hero * slots;
+        let levels = (first..first + slots).map(|j| {
+            if j == i {
+                item.level(enhancement, mastery)
+            } else {
+                items[j].level(items[j].current_enhancement, items[j].mastery)
+            }
+        });
+        let stats = self.game_data.stats_at(levels, &item.set_bonuses);
+        let change = self.objective.hero_value(&hero_weights(&items[first..]), &stats) - self.values[hero];
+        if self.above_lowest(hero) {
+            change * MAX_MIN_TIE_BREAK
+        } else {
+            change
+        }
+    }
+}
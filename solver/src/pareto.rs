@@ -0,0 +1,83 @@
+use crate::strategy::solve_with_strategy;
+use crate::types::*;
+
+/// Weight left on the other stat at the ends of the frontier, so that the
+/// leftover budget still goes to it instead of being left unspent.
+const EDGE_WEIGHT: f64 = 1e-4;
+
+/// Finds the lethality/health frontier of `input` by solving with both
+/// stats' weights set for every hero. Starting from the two ends, each pair
+/// of neighbouring points is solved again with weights that value both the
+/// same; a result beyond the line between them is a new point and splits the
+/// pair. This finds the frontier's corners, which are what a weighted sum can
+/// pick; points the heuristic strategies find that turn out dominated are
+/// dropped.
+pub fn frontier(input: &InputData, request: &ParetoRequest) -> Vec<ParetoPoint> {
+    let solve_at = |lethality: f64| {
+        let mut weighted = input.clone();
+        weighted.pareto = None;
+        weighted.shadow_prices = None;
+        let weights = StatWeights { lethality, health: 1.0 - lethality };
+        for hero in &mut weighted.heroes {
+            hero.weights = weights.clone();
+        }
+        let mut output = solve_with_strategy(weighted);
+        output.strategy = input.strategy;
+        point(weights, output)
+    };
+
+    let mut points = vec![solve_at(1.0 - EDGE_WEIGHT), solve_at(EDGE_WEIGHT)];
+    let mut pending = vec![(0, 1)];
+    while let Some((a, b)) = pending.pop() {
+        if points.len() >= request.max_points {
+            break;
+        }
+        let (p, q) = (&points[a], &points[b]);
+        let lethality_lost = p.lethality - q.lethality;
+        let health_gained = q.health - p.health;
+        if lethality_lost <= 1e-9 || health_gained <= 1e-9 {
+            continue;
+        }
+        // Under these weights `p` and `q` are worth the same.
+        let lethality = health_gained / (lethality_lost + health_gained);
+        let value = |point: &ParetoPoint| lethality * point.lethality + (1.0 - lethality) * point.health;
+        let threshold = value(p).max(value(q)) + 1e-9;
+        let found = solve_at(lethality);
+        if value(&found) > threshold {
+            points.push(found);
+            let c = points.len() - 1;
+            pending.push((c, b));
+            pending.push((a, c));
+        }
+    }
+
+    let mut frontier: Vec<ParetoPoint> = Vec::with_capacity(points.len());
+    points.sort_by(|a, b| b.lethality.total_cmp(&a.lethality).then(b.health.total_cmp(&a.health)));
+    for point in points {
+        // Sorted by lethality, a point is dominated exactly when an earlier
+        // one has at least its health.
+        if frontier.iter().all(|kept| kept.health < point.health) {
+            frontier.push(point);
+        }
+    }
+    frontier
+}
+
+fn point(weights: StatWeights, output: OptimizationOutput) -> ParetoPoint {
+    let heroes: Vec<HeroStats> = output
+        .results
+        .iter()
+        .map(|result| HeroStats {
+            hero_name: result.hero_name.clone(),
+            lethality: result.after_stats.lethality,
+            health: result.after_stats.health,
+        })
+        .collect();
+    ParetoPoint {
+        weights,
+        lethality: heroes.iter().map(|hero| hero.lethality).sum(),
+        health: heroes.iter().map(|hero| hero.health).sum(),
+        heroes,
+        output,
+    }
+}
@@ -0,0 +1,145 @@
+use crate::model::*;
+use crate::types::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpgradeKind {
+    Enhance,
+    Mastery,
+}
+
+/// A single solver decision: raise the enhancement or mastery of one item
+/// from `from` to `to`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Upgrade {
+    pub item: usize,
+    pub kind: UpgradeKind,
+    pub from: i32,
+    pub to: i32,
+}
+
+fn is_gate(game_data: &GameData, level: i32) -> bool {
+    game_data.mythic_cost(level) > 0 || game_data.mythril_cost(level) > 0
+}
+
+/// Gate levels cost mythics or mythril and are kept as separate steps.
+fn crosses_gate(game_data: &GameData, kind: UpgradeKind, from: i32, to: i32) -> bool {
+    kind == UpgradeKind::Enhance && ((from + 1)..=to).any(|level| is_gate(game_data, level))
+}
+
+/// Upgrades that take every item from `start` to `items`, for solvers that
+/// only produce final states. Mastery comes first so that every gate's
+/// prerequisite is met, and enhancement is split at each gate.
+pub fn upgrades_between(game_data: &GameData, start: &[OptimizationItem], items: &[OptimizationItem]) -> Vec<Upgrade> {
+    let mut upgrades = Vec::new();
+    for (item, (from, to)) in start.iter().zip(items).enumerate() {
+        if to.mastery > from.mastery {
+            upgrades.push(Upgrade { item, kind: UpgradeKind::Mastery, from: from.mastery, to: to.mastery });
+        }
+        let enhance = Upgrade { item, kind: UpgradeKind::Enhance, from: from.current_enhancement, to: to.current_enhancement };
+        upgrades.extend(split_at_gates(from.tables(game_data), enhance));
+    }
+    upgrades
+}
+
+/// Splits an enhancement so that every gate level is an upgrade of its own.
+fn split_at_gates(game_data: &GameData, upgrade: Upgrade) -> Vec<Upgrade> {
+    if upgrade.kind != UpgradeKind::Enhance {
+        return vec![upgrade];
+    }
+    let mut parts = Vec::new();
+    let mut lvl = upgrade.from;
+    while lvl < upgrade.to {
+        let next = match ((lvl + 1)..=upgrade.to).find(|&l| is_gate(game_data, l)) {
+            Some(gate) if gate > lvl + 1 => gate - 1,
+            Some(gate) => gate,
+            None => upgrade.to,
+        };
+        parts.push(Upgrade { from: lvl, to: next, ..upgrade });
+        lvl = next;
+    }
+    parts
+}
+
+/// Turns solver decisions into the ordered list of in-game actions.
+///
+/// Resets come first, skipping those `keeps_reset` drops. Consecutive levels of the same piece are
+/// merged into one step, except for gate levels. Merging only moves spending
+/// earlier, and after the resets every step only spends, so no balance along
+/// the plan goes negative when the final one does not.
+pub fn build_plan(input: &InputData, start: &[OptimizationItem], upgrades: &[Upgrade]) -> Vec<PlanStep> {
+    let game_data = input.game_data();
+    let mut final_enhancement: Vec<i32> = start.iter().map(|item| item.current_enhancement).collect();
+    for upgrade in upgrades.iter().filter(|u| u.kind == UpgradeKind::Enhance) {
+        final_enhancement[upgrade.item] = final_enhancement[upgrade.item].max(upgrade.to);
+    }
+
+    let mut steps: Vec<(usize, PlanAction, i32, i32)> = Vec::new();
+    let mut skipped_reset = vec![false; start.len()];
+    for (i, item) in start.iter().enumerate() {
+        if keeps_reset(item, final_enhancement[i]) {
+            steps.push((i, PlanAction::Reset, item.original_enhancement, item.current_enhancement));
+        } else if item.current_enhancement < item.original_enhancement {
+            skipped_reset[i] = true;
+        }
+    }
+
+    let mut last_step: Vec<[Option<usize>; 2]> = vec![[None; 2]; start.len()];
+    let upgrades = upgrades.iter().flat_map(|&upgrade| split_at_gates(start[upgrade.item].tables(game_data), upgrade));
+    for upgrade in upgrades {
+        let tables = start[upgrade.item].tables(game_data);
+        let (action, slot) = match upgrade.kind {
+            UpgradeKind::Enhance => (PlanAction::Enhance, 0),
+            UpgradeKind::Mastery => (PlanAction::Mastery, 1),
+        };
+        let mut from = upgrade.from;
+        if action == PlanAction::Enhance && skipped_reset[upgrade.item] {
+            let original = start[upgrade.item].original_enhancement;
+            if upgrade.to <= original {
+                continue;
+            }
+            from = from.max(original);
+        }
+
+        if let Some(j) = last_step[upgrade.item][slot] {
+            let previous = &mut steps[j];
+            if previous.3 == from
+                && !crosses_gate(tables, upgrade.kind, previous.2, previous.3)
+                && !crosses_gate(tables, upgrade.kind, from, upgrade.to)
+            {
+                previous.3 = upgrade.to;
+                continue;
+            }
+        }
+        steps.push((upgrade.item, action, from, upgrade.to));
+        last_step[upgrade.item][slot] = Some(steps.len() - 1);
+    }
+
+    let slots = &game_data.gear_set.slots;
+    let mut balance = input.budget();
+    steps
+        .into_iter()
+        .map(|(item, action, from, to)| {
+            let (cost, refund) = match action {
+                PlanAction::Reset => (
+                    Resources::default(),
+                    Resources { exp: start[item].refund, ..Resources::default() },
+                ),
+                PlanAction::Enhance => (enhancement_cost(start[item].tables(game_data), from, to), Resources::default()),
+                PlanAction::Mastery => (mastery_cost(start[item].tables(game_data), from, to), Resources::default()),
+                PlanAction::Craft => unreachable!("crafts are added to the plan after solving"),
+            };
+            balance += refund;
+            balance -= cost;
+            PlanStep {
+                action,
+                hero_name: input.heroes[item / slots.len()].name.clone(),
+                gear_type: slots[item % slots.len()].name.clone(),
+                from,
+                to,
+                cost,
+                refund,
+                balance,
+            }
+        })
+        .collect()
+}
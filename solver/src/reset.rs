@@ -0,0 +1,65 @@
+use crate::greedy::{raise_to_floors, run_greedy};
+use crate::model::*;
+use crate::types::*;
+
+/// Whether `policy` lets `gear` be reset at all: under `Below` it has to be
+/// below the threshold and under `Auto` below its tier's first gate.
+pub fn may_reset(policy: &ResetPolicy, game_data: &GameData, gear: &Gear) -> bool {
+    if !can_reset(gear) {
+        return false;
+    }
+    match policy.mode {
+        ResetMode::Never => false,
+        ResetMode::Below => gear.enhancement < policy.threshold,
+        ResetMode::Auto => {
+            let tables = game_data.tables_for(gear);
+            gear.enhancement < tables.gates.first().map_or(tables.max_enhancement + 1, |gate| gate.level)
+        }
+    }
+}
+
+/// Whether each piece, in item order, is reset under the input's policy.
+pub fn reset_decisions(input: &InputData) -> Vec<bool> {
+    let policy = input.reset_policy;
+    let game_data = input.game_data();
+    let pieces = input.heroes.iter().flat_map(|hero| game_data.pieces(&hero.gear));
+    match policy.mode {
+        ResetMode::Never => pieces.map(|_| false).collect(),
+        ResetMode::Below => pieces.map(|gear| may_reset(&policy, game_data, gear)).collect(),
+        ResetMode::Auto => auto_resets(input),
+    }
+}
+
+fn greedy_score(input: &InputData, resets: &[bool]) -> f64 {
+    let game_data = input.game_data();
+    let (mut items, budget) = prepare_items_with(input, resets);
+    let (_, budget) = raise_to_floors(game_data, &mut items, budget);
+    run_greedy(game_data, input.objective, &mut items, budget);
+    total_score(input.objective, game_data, &items)
+}
+
+/// Tries resetting one piece at a time, most EXP first, and keeps each reset
+/// that raises the score the greedy solver reaches. Only pieces below the
+/// first gate are considered (see `may_reset`), since a reset above it would
+/// throw away the mythics and mythril spent on gates.
+fn auto_resets(input: &InputData) -> Vec<bool> {
+    let game_data = input.game_data();
+    let pieces: Vec<&Gear> = input.heroes.iter().flat_map(|hero| game_data.pieces(&hero.gear)).collect();
+    let mut candidates: Vec<usize> = (0..pieces.len())
+        .filter(|&i| may_reset(&input.reset_policy, game_data, pieces[i]))
+        .collect();
+    candidates.sort_by_key(|&i| std::cmp::Reverse(pieces[i].enhancement));
+
+    let mut resets = vec![false; pieces.len()];
+    let mut best = greedy_score(input, &resets);
+    for i in candidates {
+        resets[i] = true;
+        let score = greedy_score(input, &resets);
+        if score > best + 1e-9 * best.abs().max(1.0) {
+            best = score;
+        } else {
+            resets[i] = false;
+        }
+    }
+    resets
+}
@@ -0,0 +1,133 @@
+use crate::model::*;
+use crate::plan::{build_plan, upgrades_between};
+use crate::strategy;
+use crate::types::*;
+
+/// Spreads a plan over the periods of `schedule`. The plan is solved once
+/// for the budget plus all the income of the horizon, and its steps are then
+/// taken in order, level by level, as far as each period's resources go. A
+/// step that cannot be finished holds back what it still costs, so cheaper
+/// steps after it do not eat into the savings for a gate, and the later
+/// steps of the same piece wait for it.
+///
+/// The returned output is the plan for the whole horizon, with one snapshot
+/// per period in `schedule`.
+pub fn plan(mut input: InputData, schedule: &Schedule) -> OptimizationOutput {
+    input.shop = None;
+    input.exp_items = Vec::new();
+    input.shadow_prices = None;
+    input.pareto = None;
+    input.allow_crafting = false;
+    let game_data = input.game_data().clone();
+    let base = input.clone();
+    // Validation keeps the horizon in range; saturating is only a backstop.
+    let later = schedule.periods.max(1) - 1;
+    let horizon = |held: i32, income: i32| held.saturating_add(income.saturating_mul(later));
+    let budget = base.budget();
+    input.set_budget(Resources {
+        exp: horizon(budget.exp, schedule.income.exp),
+        hammers: horizon(budget.hammers, schedule.income.hammers),
+        mythics: horizon(budget.mythics, schedule.income.mythics),
+        mythril: horizon(budget.mythril, schedule.income.mythril),
+    });
+    let mut output = strategy::run(input);
+
+    let resets: Vec<bool> = output.results.iter().flat_map(|r| r.gear.iter().map(|g| g.reset)).collect();
+    let mut levels: Vec<(i32, i32)> = base
+        .heroes
+        .iter()
+        .flat_map(|hero| game_data.pieces(&hero.gear).into_iter().map(|gear| (gear.enhancement, gear.mastery)))
+        .collect();
+    let tables: Vec<&GameData> = base
+        .heroes
+        .iter()
+        .flat_map(|hero| game_data.pieces(&hero.gear).into_iter().map(|gear| game_data.tables_for(gear)))
+        .collect();
+    let mut steps: Vec<(usize, PlanStep)> = output.plan.iter().map(|step| (item_index(&base, step), step.clone())).collect();
+    let mut heroes = base.heroes.clone();
+    let mut available = base.budget();
+    let mut periods = Vec::with_capacity(schedule.periods as usize);
+
+    for period in 1..=schedule.periods {
+        let income = if period == 1 { Resources::default() } else { schedule.income };
+        available += income;
+        let mut period_input = base.clone();
+        period_input.heroes = heroes.clone();
+        period_input.set_budget(available);
+
+        let mut reserved = Resources::default();
+        let mut waiting = vec![false; levels.len()];
+        let mut waiting_for = Vec::new();
+        steps.retain_mut(|(item, step)| {
+            if waiting[*item] {
+                return true;
+            }
+            let action = step.action;
+            let tables = tables[*item];
+            let cost = |from: i32, to: i32| match action {
+                PlanAction::Mastery => mastery_cost(tables, from, to),
+                _ => enhancement_cost(tables, from, to),
+            };
+            if step.action == PlanAction::Reset {
+                levels[*item].0 = step.to;
+                available += step.refund;
+                return false;
+            }
+            while step.from < step.to && (available - reserved - cost(step.from, step.from + 1)).is_non_negative() {
+                available -= cost(step.from, step.from + 1);
+                step.from += 1;
+            }
+            match action {
+                PlanAction::Mastery => levels[*item].1 = step.from,
+                _ => levels[*item].0 = step.from,
+            }
+            if step.from == step.to {
+                return false;
+            }
+            let remaining = cost(step.from, step.to);
+            reserved += remaining;
+            waiting[*item] = true;
+            waiting_for.push(PendingStep {
+                action: step.action,
+                hero_name: step.hero_name.clone(),
+                gear_type: step.gear_type.clone(),
+                from: step.from,
+                to: step.to,
+                remaining,
+            });
+            true
+        });
+
+        // Resets happen in the first period; later periods start from the
+        // levels the previous one left.
+        let period_resets = if period == 1 { resets.clone() } else { vec![false; levels.len()] };
+        let (start, _) = prepare_items_with(&period_input, &period_resets);
+        let mut items = start.clone();
+        for (item, &(enhancement, mastery)) in items.iter_mut().zip(&levels) {
+            item.current_enhancement = enhancement;
+            item.mastery = mastery;
+        }
+        let mut snapshot = build_output(&period_input, &start, &items);
+        snapshot.plan = build_plan(&period_input, &start, &upgrades_between(&game_data, &start, &items));
+        snapshot.strategy = output.strategy;
+        for (hero, hero_levels) in heroes.iter_mut().zip(levels.chunks(game_data.slot_count())) {
+            for (gear, &(enhancement, mastery)) in game_data.pieces_mut(&mut hero.gear).into_iter().zip(hero_levels) {
+                gear.enhancement = enhancement;
+                gear.mastery = mastery;
+            }
+        }
+        periods.push(PeriodPlan { period, income, output: snapshot, waiting_for });
+    }
+
+    output.schedule = periods;
+    output
+}
+
+/// The item a step of the horizon plan works on. Hero names are unique when
+/// a schedule is asked for.
+fn item_index(input: &InputData, step: &PlanStep) -> usize {
+    let hero = input.heroes.iter().position(|hero| hero.name == step.hero_name).expect("plan steps name input heroes");
+    let game_data = input.game_data();
+    let slot = game_data.slot_index(&step.gear_type).expect("plan steps name gear slots");
+    hero * game_data.slot_count() + slot
+}
@@ -0,0 +1,55 @@
+use crate::strategy::solve_with_strategy;
+use crate::types::*;
+
+/// Re-solves `input` with each resource raised by its step, and with each
+/// bundle added, using the input's strategy. `base_score` is the score of the
+/// unchanged input. The heuristic strategies are not monotone in the budget,
+/// so a price can come out slightly negative. A step or bundle that would
+/// take the budget past `i32::MAX` is cut down to what fits; the reported
+/// `step` is what was actually added.
+pub fn shadow_prices(input: &InputData, request: &ShadowPriceRequest, base_score: f64) -> ShadowPrices {
+    let budget = input.budget();
+    let fitting = |extra: Resources| Resources {
+        exp: extra.exp.min(i32::MAX - budget.exp),
+        hammers: extra.hammers.min(i32::MAX - budget.hammers),
+        mythics: extra.mythics.min(i32::MAX - budget.mythics),
+        mythril: extra.mythril.min(i32::MAX - budget.mythril),
+    };
+    let gain = |extra: Resources| -> f64 {
+        let extra = fitting(extra);
+        if extra == Resources::default() {
+            return 0.0;
+        }
+        let mut perturbed = input.clone();
+        perturbed.shadow_prices = None;
+        perturbed.pareto = None;
+        perturbed.set_budget(budget + extra);
+        solve_with_strategy(perturbed).total_after_score - base_score
+    };
+    let step = fitting(request.step);
+    let per_unit = |amount: i32, extra: Resources| {
+        if amount > 0 {
+            gain(extra) / amount as f64
+        } else {
+            0.0
+        }
+    };
+    let none = Resources::default();
+
+    ShadowPrices {
+        exp: per_unit(step.exp, Resources { exp: step.exp, ..none }),
+        hammers: per_unit(step.hammers, Resources { hammers: step.hammers, ..none }),
+        mythics: per_unit(step.mythics, Resources { mythics: step.mythics, ..none }),
+        mythril: per_unit(step.mythril, Resources { mythril: step.mythril, ..none }),
+        step,
+        bundles: request
+            .bundles
+            .iter()
+            .map(|bundle| BundleValue {
+                name: bundle.name.clone(),
+                contents: bundle.contents,
+                score_gain: gain(bundle.contents),
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,113 @@
+use crate::greedy::solve_greedy;
+use crate::types::*;
+
+/// Decides how many of each offer to buy with the shop's gems. Purchases are
+/// scored by the greedy solver on the budget they produce: each round buys
+/// the quantity of one offer with the best score gain per gem, trying
+/// doubling quantities because a single unit often buys nothing on its own
+/// (e.g. fewer hammers than the next mastery level costs). Units that add no
+/// score are dropped again at the end.
+pub fn plan_purchases(input: &InputData, shop: &Shop) -> ShopPlan {
+    let score = |quantities: &[i32]| -> f64 {
+        let mut trial = input.clone();
+        trial.shop = None;
+        trial.shadow_prices = None;
+        trial.strategy = Strategy::Greedy;
+        trial.set_budget(input.budget() + bought(shop, quantities));
+        solve_greedy(trial).total_after_score
+    };
+    let gems_for = |quantities: &[i32]| -> i32 {
+        shop.offers.iter().zip(quantities).map(|(offer, &q)| offer.price * q).sum()
+    };
+    let tolerance = |score: f64| 1e-9 * score.abs().max(1.0);
+
+    let mut quantities = vec![0; shop.offers.len()];
+    let mut best = score(&quantities);
+    loop {
+        let gems_left = shop.gems - gems_for(&quantities);
+        // (offer, quantity, gain per gem, score)
+        let mut best_move: Option<(usize, i32, f64, f64)> = None;
+        for (i, offer) in shop.offers.iter().enumerate() {
+            let mut affordable = offer.max_quantity - quantities[i];
+            if offer.price > 0 {
+                affordable = affordable.min(gems_left / offer.price);
+            }
+            let mut candidates = Vec::new();
+            let mut k = 1;
+            while k < affordable {
+                candidates.push(k);
+                k = k.saturating_mul(2);
+            }
+            if affordable > 0 {
+                candidates.push(affordable);
+            }
+            for k in candidates {
+                let mut trial = quantities.clone();
+                trial[i] += k;
+                let trial_score = score(&trial);
+                let gain = trial_score - best;
+                if gain <= tolerance(best) {
+                    continue;
+                }
+                let rate = if offer.price == 0 { f64::INFINITY } else { gain / (offer.price * k) as f64 };
+                // Free offers tie at an infinite rate; then the larger gain wins.
+                if best_move.is_none_or(|(_, _, best_rate, best_score)| {
+                    rate > best_rate || (rate == best_rate && trial_score > best_score)
+                }) {
+                    best_move = Some((i, k, rate, trial_score));
+                }
+            }
+        }
+        match best_move {
+            Some((i, k, _, trial_score)) => {
+                quantities[i] += k;
+                best = trial_score;
+            }
+            None => break,
+        }
+    }
+
+    // The fewest units of each offer that keep the score, found by bisection
+    // since a free offer can have billions of units.
+    for i in 0..quantities.len() {
+        let (mut low, mut high) = (0, quantities[i]);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let mut trial = quantities.clone();
+            trial[i] = mid;
+            if score(&trial) >= best - tolerance(best) {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        quantities[i] = high;
+    }
+
+    let purchases: Vec<Purchase> = shop
+        .offers
+        .iter()
+        .zip(&quantities)
+        .filter(|(_, &quantity)| quantity > 0)
+        .map(|(offer, &quantity)| Purchase {
+            name: offer.name.clone(),
+            quantity,
+            gems: offer.price * quantity,
+            contents: offer.contents * quantity,
+        })
+        .collect();
+    let gems_spent = gems_for(&quantities);
+    ShopPlan {
+        purchases,
+        gems_spent,
+        gems_leftover: shop.gems - gems_spent,
+        bought: bought(shop, &quantities),
+    }
+}
+
+fn bought(shop: &Shop, quantities: &[i32]) -> Resources {
+    shop.offers
+        .iter()
+        .zip(quantities)
+        .fold(Resources::default(), |acc, (offer, &quantity)| acc + offer.contents * quantity)
+}
@@ -0,0 +1,125 @@
+use crate::bottleneck::explain;
+use crate::craft::{apply_crafts, plan_crafts, report_crafts};
+use crate::exact::solve_exact;
+use crate::exp_items::solve_with_items;
+use crate::greedy::solve_greedy;
+use crate::local_search::solve_local_search;
+use crate::pareto::frontier;
+use crate::shadow::shadow_prices;
+use crate::shop::plan_purchases;
+use crate::types::*;
+
+pub trait GearSolver {
+    fn solve(&self, input: InputData) -> OptimizationOutput;
+}
+
+pub struct GreedySolver;
+
+impl GearSolver for GreedySolver {
+    fn solve(&self, input: InputData) -> OptimizationOutput {
+        solve_greedy(input)
+    }
+}
+
+pub struct ExactSolver;
+
+impl GearSolver for ExactSolver {
+    fn solve(&self, input: InputData) -> OptimizationOutput {
+        solve_exact(input)
+    }
+}
+
+pub struct LocalSearchSolver;
+
+impl GearSolver for LocalSearchSolver {
+    fn solve(&self, input: InputData) -> OptimizationOutput {
+        solve_local_search(input)
+    }
+}
+
+/// The single place where strategies are registered.
+pub fn solver_for(strategy: Strategy) -> &'static dyn GearSolver {
+    match strategy {
+        Strategy::Greedy => &GreedySolver,
+        Strategy::Exact => &ExactSolver,
+        Strategy::LocalSearch => &LocalSearchSolver,
+    }
+}
+
+/// Solves with the input's strategy, fitting the plan to the input's EXP
+/// materials when it has any.
+pub fn solve_with_strategy(input: InputData) -> OptimizationOutput {
+    let solver = solver_for(input.strategy);
+    if input.exp_items.is_empty() {
+        solver.solve(input)
+    } else {
+        solve_with_items(input, |trial| solver.solve(trial))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod clock {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = performance, js_name = now)]
+        fn performance_now() -> f64;
+    }
+
+    pub fn now_ms() -> f64 {
+        performance_now()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod clock {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    pub fn now_ms() -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64() * 1000.0)
+            .unwrap_or(0.0)
+    }
+}
+
+/// Runs the strategy selected in `input` and records it in the output
+/// together with the time it took. Shop purchases are decided first and
+/// added to the budget, then crafts when `allowCrafting` is set. Shadow
+/// prices and the Pareto frontier, when asked for, are added afterwards for
+/// the crafted pieces and not counted in the runtime.
+pub fn run(mut input: InputData) -> OptimizationOutput {
+    let strategy = input.strategy;
+    let started = clock::now_ms();
+    let shop = input.shop.take().map(|shop| {
+        let plan = plan_purchases(&input, &shop);
+        input.set_budget(input.budget() + plan.bought);
+        plan
+    });
+    let crafting = input.allow_crafting.then(|| {
+        let (crafts, options) = plan_crafts(&input);
+        let crafted = apply_crafts(&input, &crafts);
+        let original = std::mem::replace(&mut input, crafted);
+        (original, crafts, options)
+    });
+    let shadow = input.shadow_prices.clone().map(|request| (request, input.clone()));
+    let pareto = input.pareto.clone().map(|request| (request, input.clone()));
+    let solved = input.clone();
+    let mut output = solve_with_strategy(input);
+    output.strategy = strategy;
+    output.runtime_ms = clock::now_ms() - started;
+    output.shop = shop;
+    if let Some((original, crafts, options)) = crafting {
+        report_crafts(&original, &crafts, &mut output);
+        output.crafting = options;
+    }
+    (output.bottlenecks, output.resource_ranking) = explain(&solved, &output);
+    if let Some((request, input)) = shadow {
+        output.shadow_prices = Some(shadow_prices(&input, &request, output.total_after_score));
+    }
+    if let Some((request, input)) = pareto {
+        output.pareto = frontier(&input, &request);
+    }
+    output
+}
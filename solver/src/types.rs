@@ -1,17 +1,45 @@
 use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+use crate::error::SolveResult;
+
+pub use crate::game_data::{GameData, Gate, GearSet, PieceLevel, SetBonus, SlotDefinition, SlotStat};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct Gear {
     pub mastery: i32,
     pub enhancement: i32,
+    /// Never reset or upgrade this piece.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+    /// Never reset this piece, even below enhancement 101.
+    #[serde(rename = "noReset", default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_reset: bool,
+    /// The piece must end at or above this enhancement.
+    #[serde(rename = "minEnhancement", default, skip_serializing_if = "Option::is_none")]
+    pub min_enhancement: Option<i32>,
+    /// The piece must not be enhanced past this level.
+    #[serde(rename = "maxEnhancement", default, skip_serializing_if = "Option::is_none")]
+    pub max_enhancement: Option<i32>,
+    /// Raise mastery to exactly this level, and no further.
+    #[serde(rename = "targetMastery", default, skip_serializing_if = "Option::is_none")]
+    pub target_mastery: Option<i32>,
+    /// The piece's gear tier, one of the game data's; the highest if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct HeroGear {
-    pub helmet: Gear,
-    pub gloves: Gear,
-    pub breastplate: Gear,
-    pub boots: Gear,
+/// A hero's pieces keyed by the slot names of the game data's gear set.
+/// Missing slots are empty pieces.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(transparent)]
+pub struct HeroGear(pub BTreeMap<String, Gear>);
+
+impl<const N: usize> From<[(&str, Gear); N]> for HeroGear {
+    fn from(pieces: [(&str, Gear); N]) -> Self {
+        HeroGear(pieces.into_iter().map(|(slot, gear)| (slot.to_string(), gear)).collect())
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -25,9 +53,13 @@ pub struct HeroWeights {
     pub name: String,
     pub gear: HeroGear,
     pub weights: StatWeights,
+    /// Bonuses of the set this hero's gear belongs to, on top of the ones
+    /// every hero gets from the game data's gear set.
+    #[serde(rename = "setBonuses", default, skip_serializing_if = "Vec::is_empty")]
+    pub set_bonuses: Vec<SetBonus>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct Stats {
     pub lethality: f64,
     pub health: f64,
@@ -45,6 +77,19 @@ pub struct GearResult {
     pub current_enhancement: i32,
     #[serde(rename = "recommendedEnhancement")]
     pub recommended_enhancement: i32,
+    /// Resources spent on this piece by the plan.
+    #[serde(default)]
+    pub spent: Resources,
+    /// EXP given back by resetting this piece.
+    #[serde(rename = "reclaimedExp", default)]
+    pub reclaimed_exp: i32,
+    /// Whether the plan resets this piece.
+    #[serde(default)]
+    pub reset: bool,
+    /// The tier the plan crafts this piece into. `current` levels are then
+    /// the old piece's, and `spent` includes the craft.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crafted: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -60,110 +105,785 @@ pub struct OptimizationResult {
     pub before_score: f64,
     #[serde(rename = "afterScore")]
     pub after_score: f64,
+    #[serde(default)]
+    pub spent: Resources,
+    #[serde(rename = "reclaimedExp", default)]
+    pub reclaimed_exp: i32,
+    /// Names of the set bonuses the recommended gear earns.
+    #[serde(rename = "setBonuses", default)]
+    pub set_bonuses: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct Resources {
+    pub exp: i32,
+    pub hammers: i32,
+    pub mythics: i32,
+    pub mythril: i32,
+}
+
+impl Resources {
+    /// True when no resource is overdrawn.
+    pub fn is_non_negative(&self) -> bool {
+        self.exp >= 0 && self.hammers >= 0 && self.mythics >= 0 && self.mythril >= 0
+    }
+}
+
+// The arithmetic saturates: amounts beyond `i32::MAX` cannot be spent
+// anyway, and the solver must not panic on them.
+impl Add for Resources {
+    type Output = Resources;
+
+    fn add(self, other: Resources) -> Resources {
+        Resources {
+            exp: self.exp.saturating_add(other.exp),
+            hammers: self.hammers.saturating_add(other.hammers),
+            mythics: self.mythics.saturating_add(other.mythics),
+            mythril: self.mythril.saturating_add(other.mythril),
+        }
+    }
+}
+
+impl Sub for Resources {
+    type Output = Resources;
+
+    fn sub(self, other: Resources) -> Resources {
+        Resources {
+            exp: self.exp.saturating_sub(other.exp),
+            hammers: self.hammers.saturating_sub(other.hammers),
+            mythics: self.mythics.saturating_sub(other.mythics),
+            mythril: self.mythril.saturating_sub(other.mythril),
+        }
+    }
+}
+
+impl AddAssign for Resources {
+    fn add_assign(&mut self, other: Resources) {
+        *self = *self + other;
+    }
 }
 
+impl Mul<i32> for Resources {
+    type Output = Resources;
+
+    fn mul(self, factor: i32) -> Resources {
+        Resources {
+            exp: self.exp.saturating_mul(factor),
+            hammers: self.hammers.saturating_mul(factor),
+            mythics: self.mythics.saturating_mul(factor),
+            mythril: self.mythril.saturating_mul(factor),
+        }
+    }
+}
+
+impl SubAssign for Resources {
+    fn sub_assign(&mut self, other: Resources) {
+        *self = *self - other;
+    }
+}
+
+/// How far the greedy allocation is from the best one found by the exact solver.
 #[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GreedyGap {
+    #[serde(rename = "greedyScore")]
+    pub greedy_score: f64,
+    #[serde(rename = "optimalScore")]
+    pub optimal_score: f64,
+    #[serde(rename = "absoluteGap")]
+    pub absolute_gap: f64,
+    #[serde(rename = "relativeGap")]
+    pub relative_gap: f64,
+    /// No allocation can score higher than this. Equals `optimal_score` when
+    /// `proven_optimal` is set.
+    #[serde(rename = "upperBound")]
+    pub upper_bound: f64,
+    /// False when the search hit its node limit before closing the bound.
+    #[serde(rename = "provenOptimal")]
+    pub proven_optimal: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PlanAction {
+    /// Dismantle the piece back to enhancement 0 and get its EXP back.
+    Reset,
+    Mastery,
+    Enhance,
+    /// Replace the piece with a newly crafted one of a higher tier.
+    Craft,
+}
+
+/// One in-game action of the upgrade plan. `from` and `to` are enhancement
+/// levels for `reset`, `craft` and `enhance`, and mastery levels for
+/// `mastery`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlanStep {
+    pub action: PlanAction,
+    #[serde(rename = "heroName")]
+    pub hero_name: String,
+    #[serde(rename = "type")]
+    pub gear_type: String,
+    pub from: i32,
+    pub to: i32,
+    pub cost: Resources,
+    pub refund: Resources,
+    /// Resources left after this step.
+    pub balance: Resources,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct OptimizationOutput {
     pub results: Vec<OptimizationResult>,
     #[serde(rename = "totalBeforeScore")]
     pub total_before_score: f64,
     #[serde(rename = "totalAfterScore")]
     pub total_after_score: f64,
+    #[serde(rename = "greedyGap", default, skip_serializing_if = "Option::is_none")]
+    pub greedy_gap: Option<GreedyGap>,
+    #[serde(default)]
+    pub strategy: Strategy,
+    #[serde(rename = "runtimeMs", default)]
+    pub runtime_ms: f64,
+    #[serde(default)]
+    pub plan: Vec<PlanStep>,
+    /// Total resources spent across all heroes.
+    #[serde(default)]
+    pub used: Resources,
+    /// Budget plus reclaimed EXP minus `used`.
+    #[serde(default)]
+    pub leftover: Resources,
+    #[serde(rename = "reclaimedExp", default)]
+    pub reclaimed_exp: i32,
+    /// `version` of the game tables the plan was computed with.
+    #[serde(rename = "gameDataVersion", default)]
+    pub game_data_version: String,
+    #[serde(rename = "shadowPrices", default, skip_serializing_if = "Option::is_none")]
+    pub shadow_prices: Option<ShadowPrices>,
+    /// Lethality/health trade-offs, when asked for with `pareto`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pareto: Vec<ParetoPoint>,
+    /// What to buy from the shop; its contents are already part of the
+    /// budget behind `used` and `leftover`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shop: Option<ShopPlan>,
+    #[serde(rename = "expItems", default, skip_serializing_if = "Option::is_none")]
+    pub exp_items: Option<ExpItemPlan>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<GoalResult>,
+    /// Pieces the plan leaves short of a next step it cannot pay for or
+    /// that their constraints forbid.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bottlenecks: Vec<Bottleneck>,
+    /// The resources behind `bottlenecks`, most binding first.
+    #[serde(rename = "resourceRanking", default, skip_serializing_if = "Vec::is_empty")]
+    pub resource_ranking: Vec<ResourcePressure>,
+    /// Every craft that was weighed, when `allowCrafting` is set.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crafting: Vec<CraftOption>,
+    /// The plan split into periods when the input has a `schedule`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedule: Vec<PeriodPlan>,
+    /// Input values that lenient validation had to change.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ValidationWarning>,
+}
+
+/// Score gained per extra unit of each resource, measured by re-solving with
+/// `step` more of it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ShadowPrices {
+    pub exp: f64,
+    pub hammers: f64,
+    pub mythics: f64,
+    pub mythril: f64,
+    pub step: Resources,
+    pub bundles: Vec<BundleValue>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct InputData {
-    pub heroes: Vec<HeroWeights>,
-    pub exp: i32,
-    pub hammers: i32,
-    pub mythics: i32,
-    pub mythril: i32,
+pub struct BundleValue {
+    pub name: String,
+    pub contents: Resources,
+    /// Score gained by adding the whole bundle to the budget.
+    #[serde(rename = "scoreGain")]
+    pub score_gain: f64,
 }
 
-pub const EXP_COSTS: [i32; 201] = [
-    0, 10, 25, 45, 70, 100, 135, 175, 220, 270, 325, 385, 450, 520, 595, 675, 760, 850, 945, 1045,
-    1150, 1260, 1375, 1495, 1620, 1750, 1885, 2025, 2170, 2320, 2480, 2650, 2830, 3020, 3220, 3430,
-    3650, 3880, 4120, 4370, 4640, 4930, 5240, 5570, 5920, 6290, 6680, 7090, 7520, 7970, 8440, 8930,
-    9440, 9970, 10520, 11090, 11680, 12290, 12920, 13570, 14250, 14960, 15700, 16470, 17270, 18100,
-    18960, 19850, 20770, 21720, 22710, 23740, 24810, 25920, 27070, 28260, 29490, 30760, 32070,
-    33420, 34820, 36270, 37770, 39320, 40920, 42570, 44270, 46020, 47820, 49670, 51570, 53520,
-    55520, 57570, 59670, 61820, 64020, 66270, 68570, 70920, 73320, 73320, 75820, 78370, 80970,
-    83620, 86320, 89070, 91870, 94720, 97620, 100570, 103570, 106620, 109720, 112870, 116070,
-    119320, 122620, 125970, 125970, 129470, 133020, 136620, 140270, 143970, 147720, 151520, 155370,
-    159270, 163220, 167220, 171270, 175370, 179520, 183720, 187970, 192270, 196620, 201020, 201020,
-    205470, 209970, 214520, 219120, 223770, 228470, 233220, 238020, 242870, 247770, 252720, 257720,
-    262770, 267870, 273020, 278220, 283470, 288770, 294120, 294120, 299620, 305220, 310920, 316720,
-    322620, 328620, 334720, 340920, 347220, 353620, 360120, 366720, 373420, 380220, 387120, 394120,
-    401220, 408420, 415720, 415720, 423220, 430820, 438520, 446320, 454220, 462220, 470320, 478520,
-    486820, 495220, 503720, 512320, 521020, 529820, 538720, 547720, 556820, 566020, 575320, 575320,
-];
-
-pub fn exp_cost(enhancement: i32) -> i32 {
-    if enhancement < 0 || enhancement >= EXP_COSTS.len() as i32 {
-        return 0;
-    }
-    EXP_COSTS[enhancement as usize]
+/// A set of resources bought together, such as a shop pack.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Bundle {
+    pub name: String,
+    pub contents: Resources,
 }
 
-pub fn hammer_cost(target_level: i32) -> i32 {
-    if target_level <= 0 || target_level > 20 {
-        return 0; // Or handle error? For now 0 cost for invalid levels implies we shouldn't call it.
-    }
-    10 * target_level
+/// Asks the solver to price resources. Costs come in steps (EXP in multiples
+/// of 5, hammers per mastery level), so a single unit rarely changes the plan;
+/// each price is the gain from `step` more units divided by `step`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShadowPriceRequest {
+    #[serde(default = "default_shadow_step")]
+    pub step: Resources,
+    #[serde(default)]
+    pub bundles: Vec<Bundle>,
+}
+
+fn default_shadow_step() -> Resources {
+    Resources { exp: 5000, hammers: 200, mythics: 5, mythril: 10 }
 }
 
-pub fn mythril_cost(target_level: i32) -> i32 {
-    match target_level {
-        120 => 10,
-        140 => 20,
-        160 => 30,
-        180 => 40,
-        200 => 50,
-        _ => 0,
+impl Default for ShadowPriceRequest {
+    fn default() -> Self {
+        ShadowPriceRequest { step: default_shadow_step(), bundles: Vec::new() }
     }
 }
 
-pub fn mythic_cost(target_level: i32) -> i32 {
-    match target_level {
-        101 => 2,
-        120 => 3,
-        140 => 5,
-        160 => 5,
-        180 => 10,
-        200 => 10,
-        _ => 0,
+/// The next step of a piece that the plan could not take: its enhancement
+/// level, or its mastery level once enhancement is at its cap.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Bottleneck {
+    #[serde(rename = "heroName")]
+    pub hero_name: String,
+    #[serde(rename = "type")]
+    pub gear_type: String,
+    /// `enhance` or `mastery`, with `from` and `to` levels of that kind.
+    pub action: PlanAction,
+    pub from: i32,
+    pub to: i32,
+    /// What the step costs, its mastery prerequisite included.
+    pub cost: Resources,
+    /// What the leftover budget is missing of `cost`.
+    pub missing: Resources,
+    /// Mastery the step requires, when above the piece's.
+    #[serde(rename = "requiredMastery", default, skip_serializing_if = "Option::is_none")]
+    pub required_mastery: Option<i32>,
+    /// E.g. "blocked at 100→101: need 2 mythics + mastery 10 (costs 550 hammers)".
+    pub message: String,
+}
+
+/// How many blocked steps one resource holds back.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ResourcePressure {
+    /// `exp`, `hammers`, `mythics` or `mythril`.
+    pub resource: String,
+    pub blocked: usize,
+    /// Total missing over those steps.
+    pub missing: i32,
+}
+
+/// The input of `cost`: two states of one piece, priced with `gameData` or
+/// the built-in tables.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CostQuery {
+    pub from: Gear,
+    pub to: Gear,
+    #[serde(rename = "gameData", default, skip_serializing_if = "Option::is_none")]
+    pub game_data: Option<GameData>,
+}
+
+/// What taking a piece from one state to another costs.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TransitionCost {
+    pub cost: Resources,
+    /// Mastery the piece ends at: the target's, raised to what its
+    /// enhancement needs.
+    pub mastery: i32,
+    /// The gates crossed on the way, lowest first.
+    pub gates: Vec<Gate>,
+}
+
+/// A rule a proposed gear state breaks. `field` is the JSON path of the
+/// input value the rule is about, e.g. `heroes[0].gear.helmet.mastery`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlanViolation {
+    pub field: String,
+    pub message: String,
+}
+
+/// What `verify_plan` finds out about a proposed final gear state.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PlanCheck {
+    /// No rule is broken, the budget included.
+    pub feasible: bool,
+    /// Everything the upgrades and crafts consume, gate costs and mastery
+    /// prerequisites included.
+    pub required: Resources,
+    /// EXP given back by the resets the plan implies.
+    #[serde(rename = "reclaimedExp")]
+    pub reclaimed_exp: i32,
+    /// What the budget plus the reclaimed EXP is missing of `required`.
+    pub shortfall: Resources,
+    pub violations: Vec<PlanViolation>,
+}
+
+/// The input of `verify`: the final `gear` proposed for each hero of
+/// `input`, in hero order.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlanProposal {
+    pub input: InputData,
+    pub gear: Vec<HeroGear>,
+}
+
+/// One player of an alliance batch, with their own input.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerInput {
+    pub name: String,
+    pub input: InputData,
+}
+
+/// The players `solve_batch` solves in one call.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BatchInput {
+    pub players: Vec<PlayerInput>,
+    /// Extra mythics each player is solved again with, to find the players
+    /// whose gain mythics hold back.
+    #[serde(rename = "mythicStep", default = "default_mythic_step")]
+    pub mythic_step: i32,
+}
+
+fn default_mythic_step() -> i32 {
+    default_shadow_step().mythics
+}
+
+/// A player's output, or why their input was rejected.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlayerOutput {
+    pub name: String,
+    pub result: SolveResult,
+}
+
+/// A player who would gain `score_gain` more with `mythicStep` more mythics.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MythicBlock {
+    pub name: String,
+    #[serde(rename = "scoreGain")]
+    pub score_gain: f64,
+}
+
+/// How many pieces of the alliance are at enhancement `from..=to`, before
+/// and after the recommended upgrades.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LevelBucket {
+    pub from: i32,
+    pub to: i32,
+    pub current: usize,
+    pub recommended: usize,
+}
+
+/// Totals over the players whose input was solved.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AllianceSummary {
+    pub solved: usize,
+    /// Players whose input was rejected.
+    pub failed: Vec<String>,
+    #[serde(rename = "totalBeforeScore")]
+    pub total_before_score: f64,
+    #[serde(rename = "totalAfterScore")]
+    pub total_after_score: f64,
+    #[serde(rename = "totalScoreGain")]
+    pub total_score_gain: f64,
+    /// Most held back first.
+    #[serde(rename = "mythicBlocked")]
+    pub mythic_blocked: Vec<MythicBlock>,
+    #[serde(rename = "enhancementLevels")]
+    pub enhancement_levels: Vec<LevelBucket>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchOutput {
+    pub players: Vec<PlayerOutput>,
+    pub summary: AllianceSummary,
+}
+
+/// Asks for the lethality/health frontier, with at most `max_points` points.
+/// Both ends are always solved, so `max_points` is at least 2.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ParetoRequest {
+    #[serde(rename = "maxPoints", default = "default_pareto_points")]
+    pub max_points: usize,
+}
+
+fn default_pareto_points() -> usize {
+    20
+}
+
+impl Default for ParetoRequest {
+    fn default() -> Self {
+        ParetoRequest { max_points: default_pareto_points() }
     }
 }
 
-pub fn required_mastery(target_level: i32) -> i32 {
-    if target_level <= 100 {
-        0
-    } else if target_level < 120 {
-        10
-    } else {
-        11 + (target_level - 120) / 20
+/// One allocation no other found allocation beats on both lethality and
+/// health. `weights` are the stat weights, given to every hero, under which
+/// the solver picked it.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ParetoPoint {
+    pub weights: StatWeights,
+    /// Totals over all heroes.
+    pub lethality: f64,
+    pub health: f64,
+    pub heroes: Vec<HeroStats>,
+    pub output: OptimizationOutput,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HeroStats {
+    #[serde(rename = "heroName")]
+    pub hero_name: String,
+    pub lethality: f64,
+    pub health: f64,
+}
+
+/// Something the shop sells, up to `max_quantity` times, for `price` gems.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShopOffer {
+    pub name: String,
+    pub price: i32,
+    pub contents: Resources,
+    #[serde(rename = "maxQuantity")]
+    pub max_quantity: i32,
+}
+
+/// Gems (or event tokens) the player can convert into resources.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Shop {
+    pub gems: i32,
+    pub offers: Vec<ShopOffer>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Purchase {
+    pub name: String,
+    pub quantity: i32,
+    /// Gems paid for all `quantity` units.
+    pub gems: i32,
+    /// Resources received for all `quantity` units.
+    pub contents: Resources,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ShopPlan {
+    pub purchases: Vec<Purchase>,
+    #[serde(rename = "gemsSpent")]
+    pub gems_spent: i32,
+    #[serde(rename = "gemsLeftover")]
+    pub gems_leftover: i32,
+    /// Resources received from all purchases together.
+    pub bought: Resources,
+}
+
+/// Crafting one piece into a higher tier against enhancing the piece the
+/// player has, both scored by the greedy solver on the whole roster.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CraftOption {
+    #[serde(rename = "heroName")]
+    pub hero_name: String,
+    #[serde(rename = "type")]
+    pub gear_type: String,
+    #[serde(rename = "fromTier", default, skip_serializing_if = "Option::is_none")]
+    pub from_tier: Option<String>,
+    #[serde(rename = "toTier")]
+    pub to_tier: String,
+    pub cost: Resources,
+    /// Score without crafting anything.
+    #[serde(rename = "enhanceScore")]
+    pub enhance_score: f64,
+    /// Score with only this piece crafted.
+    #[serde(rename = "craftScore")]
+    pub craft_score: f64,
+    /// Whether the plan crafts it.
+    pub chosen: bool,
+}
+
+/// `count` enhancement materials worth `exp` EXP each.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExpItemStack {
+    pub exp: i32,
+    pub count: i32,
+}
+
+/// The EXP materials to feed one piece.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExpFeed {
+    #[serde(rename = "heroName")]
+    pub hero_name: String,
+    #[serde(rename = "type")]
+    pub gear_type: String,
+    pub items: Vec<ExpItemStack>,
+    /// EXP taken from the loose pool (`exp` and reset refunds).
+    #[serde(rename = "looseExp")]
+    pub loose_exp: i32,
+    /// EXP fed beyond the piece's final level. It is progress towards the
+    /// next level at best, so the plan does not count on it.
+    #[serde(rename = "wastedExp")]
+    pub wasted_exp: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ExpItemPlan {
+    pub feeds: Vec<ExpFeed>,
+    /// Materials left over, largest first.
+    pub leftover: Vec<ExpItemStack>,
+    #[serde(rename = "wastedExp")]
+    pub wasted_exp: i32,
+}
+
+/// Raise one piece of a hero, or every slot of the hero when `gear_type` is
+/// missing, to at least `enhancement` and `mastery`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PieceTarget {
+    #[serde(rename = "heroName")]
+    pub hero_name: String,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub gear_type: Option<String>,
+    #[serde(default)]
+    pub enhancement: i32,
+    #[serde(default)]
+    pub mastery: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TargetStat {
+    /// The weighted score, as in `totalAfterScore`.
+    #[default]
+    Score,
+    Lethality,
+    Health,
+}
+
+/// Reach at least `at_least` of `stat`, for one hero or, when `hero_name` is
+/// missing, summed over all heroes.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScoreTarget {
+    #[serde(rename = "heroName", default, skip_serializing_if = "Option::is_none")]
+    pub hero_name: Option<String>,
+    #[serde(default)]
+    pub stat: TargetStat,
+    #[serde(rename = "atLeast")]
+    pub at_least: f64,
+}
+
+/// Relative value of one unit of each resource, used to pick the cheapest
+/// way to reach a score target.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct CostWeights {
+    pub exp: f64,
+    pub hammers: f64,
+    pub mythics: f64,
+    pub mythril: f64,
+}
+
+/// Asks for the resources needed to reach the targets instead of the best
+/// use of the budget.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Goal {
+    #[serde(default)]
+    pub pieces: Vec<PieceTarget>,
+    #[serde(default)]
+    pub scores: Vec<ScoreTarget>,
+    /// Defaults to one over the amount of each resource needed to max a
+    /// single piece from scratch.
+    #[serde(rename = "costWeights", default, skip_serializing_if = "Option::is_none")]
+    pub cost_weights: Option<CostWeights>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GoalResult {
+    /// Resources the upgrade path needs in total.
+    pub required: Resources,
+    /// What the budget in the input is missing of `required`.
+    pub shortfall: Resources,
+    /// Value of each score target's stat after the upgrades, in input order.
+    pub achieved: Vec<f64>,
+}
+
+/// Plan over `periods` periods (e.g. weeks), receiving `income` at the
+/// start of every period after the first.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Schedule {
+    pub periods: i32,
+    pub income: Resources,
+}
+
+/// A plan step left unfinished at the end of a period, with what it still
+/// costs. Its resources are held back from later steps.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PendingStep {
+    pub action: PlanAction,
+    #[serde(rename = "heroName")]
+    pub hero_name: String,
+    #[serde(rename = "type")]
+    pub gear_type: String,
+    pub from: i32,
+    pub to: i32,
+    pub remaining: Resources,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PeriodPlan {
+    /// 1 for the current period.
+    pub period: i32,
+    /// Income received at the start of this period.
+    pub income: Resources,
+    /// The upgrades of this period, starting from the gear and budget at the
+    /// start of the period. Its `leftover` carries over to the next one.
+    pub output: OptimizationOutput,
+    /// Steps being saved for.
+    #[serde(rename = "waitingFor")]
+    pub waiting_for: Vec<PendingStep>,
+}
+
+/// An input value that was out of range and has been clamped.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ValidationWarning {
+    /// JSON path of the value, e.g. `heroes[0].gear.helmet.mastery`.
+    pub field: String,
+    pub message: String,
+}
+
+/// What validation does with an out-of-range input value.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ValidationMode {
+    /// Reject the input with a validation error.
+    #[default]
+    Strict,
+    /// Clamp the value into range and report a warning.
+    Lenient,
+}
+
+/// Which solver `solve` runs. See `strategy::solver_for` for the registry.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    #[default]
+    Greedy,
+    Exact,
+    LocalSearch,
+}
+
+/// What the solvers maximize. Each hero's stats are first weighted, giving
+/// `l = lethality * weights.lethality` and `h = health * weights.health`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Objective {
+    /// The sum of `l + h` over all heroes.
+    #[default]
+    Linear,
+    /// The sum of `(1 + l) * (1 + h)`, for stats that multiply in combat.
+    Multiplicative,
+    /// The sum of `ln(1 + l) + ln(1 + h)`, for diminishing returns.
+    LogUtility,
+    /// The lowest `l + h` of any hero, with the total breaking ties.
+    MaxMin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ResetMode {
+    /// Never reset any piece.
+    Never,
+    /// Reset every piece whose enhancement is below `threshold`.
+    #[default]
+    Below,
+    /// Reset a piece below 101 only when the solver gets a better score for it.
+    Auto,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ResetPolicy {
+    #[serde(default)]
+    pub mode: ResetMode,
+    #[serde(default = "default_reset_threshold")]
+    pub threshold: i32,
+    /// Share of the piece's EXP that a reset gives back.
+    #[serde(rename = "refundPercent", default = "default_refund_percent")]
+    pub refund_percent: f64,
+}
+
+fn default_reset_threshold() -> i32 {
+    101
+}
+
+fn default_refund_percent() -> f64 {
+    100.0
+}
+
+impl Default for ResetPolicy {
+    fn default() -> Self {
+        ResetPolicy {
+            mode: ResetMode::default(),
+            threshold: default_reset_threshold(),
+            refund_percent: default_refund_percent(),
+        }
     }
 }
 
-pub fn mastery_mythic_cost(target_mastery: i32) -> i32 {
-    if target_mastery <= 10 {
-        0
-    } else {
-        target_mastery - 10
+impl ResetPolicy {
+    /// EXP given back by resetting a piece at `enhancement`.
+    pub fn refund(&self, game_data: &GameData, enhancement: i32) -> i32 {
+        (game_data.exp_cost(enhancement) as f64 * self.refund_percent / 100.0).floor() as i32
     }
 }
 
-pub fn stat(enh: i32, mastery: i32) -> f64 {
-    let enh_f = enh as f64;
-    let mastery_f = mastery as f64;
-    let base = 0.15 + enh_f.min(100.0) * 0.0035 + (enh_f - 100.0).max(0.0) * 0.005;
-    base * (1.0 + mastery_f * 0.1)
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct InputData {
+    pub heroes: Vec<HeroWeights>,
+    pub exp: i32,
+    pub hammers: i32,
+    pub mythics: i32,
+    pub mythril: i32,
+    #[serde(default)]
+    pub strategy: Strategy,
+    #[serde(default)]
+    pub objective: Objective,
+    #[serde(rename = "resetPolicy", default)]
+    pub reset_policy: ResetPolicy,
+    #[serde(default)]
+    pub validation: ValidationMode,
+    /// Game tables to use instead of the built-in ones.
+    #[serde(rename = "gameData", default, skip_serializing_if = "Option::is_none")]
+    pub game_data: Option<GameData>,
+    #[serde(rename = "shadowPrices", default, skip_serializing_if = "Option::is_none")]
+    pub shadow_prices: Option<ShadowPriceRequest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pareto: Option<ParetoRequest>,
+    /// EXP held as discrete materials, on top of the loose `exp`.
+    #[serde(rename = "expItems", default, skip_serializing_if = "Vec::is_empty")]
+    pub exp_items: Vec<ExpItemStack>,
+    /// When set, `solve` reports the resources needed to reach the goal
+    /// instead of spending the budget. Resets, shop offers, EXP materials and
+    /// shadow prices are ignored in this mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<Goal>,
+    /// Spread the plan over several periods of income. Shop offers, EXP
+    /// materials, shadow prices and the Pareto frontier are ignored in this
+    /// mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>,
+    /// Offers to buy resources from, decided together with the upgrades.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shop: Option<Shop>,
+    /// Weigh crafting pieces into the higher tiers that have a `craftCost`
+    /// against enhancing them.
+    #[serde(rename = "allowCrafting", default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_crafting: bool,
 }
 
-pub fn calculate_stats(gear: &HeroGear) -> Stats {
-    let lethality = stat(gear.helmet.enhancement, gear.helmet.mastery)
-        + stat(gear.boots.enhancement, gear.boots.mastery);
-    let health = stat(gear.breastplate.enhancement, gear.breastplate.mastery)
-        + stat(gear.gloves.enhancement, gear.gloves.mastery);
-    Stats { lethality, health }
+impl InputData {
+    pub fn budget(&self) -> Resources {
+        Resources { exp: self.exp, hammers: self.hammers, mythics: self.mythics, mythril: self.mythril }
+    }
+
+    pub fn set_budget(&mut self, budget: Resources) {
+        self.exp = budget.exp;
+        self.hammers = budget.hammers;
+        self.mythics = budget.mythics;
+        self.mythril = budget.mythril;
+    }
+
+    pub fn game_data(&self) -> &GameData {
+        self.game_data.as_ref().unwrap_or_else(|| GameData::builtin())
+    }
 }
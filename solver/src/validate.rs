@@ -0,0 +1,303 @@
+use std::ops::RangeInclusive;
+
+use crate::error::SolverError;
+use crate::types::*;
+
+/// Each period of a schedule gets a full output; five years of weeks is
+/// plenty.
+const MAX_PERIODS: i32 = 260;
+
+/// Checks `input` before any solver runs. Inconsistent game data is always
+/// rejected. Negative budgets, levels beyond the caps and weights that are
+/// negative or not numbers are rejected in strict mode; in lenient mode they
+/// are clamped into range and a warning is returned for each.
+pub fn validate_input(input: &mut InputData) -> Result<Vec<ValidationWarning>, SolverError> {
+    if let Some(game_data) = &input.game_data {
+        game_data.validate().map_err(|e| e.within("gameData"))?;
+    }
+    let game_data = input.game_data();
+    let enhancement_range = 0..=game_data.max_enhancement;
+    let mastery_range = 0..=game_data.max_mastery;
+    // Caps of each tier, by rank.
+    let tier_ranges: Vec<(RangeInclusive<i32>, RangeInclusive<i32>)> = (0..=game_data.top_tier())
+        .map(|tier| {
+            let tables = game_data.tables(tier);
+            (0..=tables.max_enhancement, 0..=tables.max_mastery)
+        })
+        .collect();
+    let slots: Vec<String> = game_data.gear_set.slots.iter().map(|slot| slot.name.clone()).collect();
+    let mut gear_tiers = Vec::with_capacity(input.heroes.len());
+    for (i, hero) in input.heroes.iter().enumerate() {
+        for (j, bonus) in hero.set_bonuses.iter().enumerate() {
+            game_data.check_bonus(bonus, &format!("heroes[{}].setBonuses[{}]", i, j))?;
+        }
+        let mut tiers = Vec::with_capacity(hero.gear.0.len());
+        for (gear_type, gear) in &hero.gear.0 {
+            let Some(tier) = game_data.tier_rank(gear.tier.as_deref()) else {
+                return Err(SolverError::validation(
+                    format!("heroes[{}].gear.{}.tier", i, gear_type),
+                    format!("unknown tier `{}`", gear.tier.as_deref().unwrap_or_default()),
+                ));
+            };
+            tiers.push((gear_type.clone(), tier));
+        }
+        gear_tiers.push(tiers);
+    }
+    let mut checker = Checker { mode: input.validation, warnings: Vec::new() };
+
+    for (field, amount) in [
+        ("exp", &mut input.exp),
+        ("hammers", &mut input.hammers),
+        ("mythics", &mut input.mythics),
+        ("mythril", &mut input.mythril),
+    ] {
+        checker.clamp(field.to_string(), amount, 0..=i32::MAX)?;
+    }
+
+    if let Some(request) = &mut input.shadow_prices {
+        checker.resources("shadowPrices.step", &mut request.step)?;
+        for (i, bundle) in request.bundles.iter_mut().enumerate() {
+            checker.resources(&format!("shadowPrices.bundles[{}].contents", i), &mut bundle.contents)?;
+        }
+    }
+
+    if let Some(request) = &mut input.pareto {
+        if request.max_points < 2 {
+            checker.report(
+                "pareto.maxPoints".to_string(),
+                format!("must be at least 2 for the two ends, got {}", request.max_points),
+                2,
+            )?;
+            request.max_points = 2;
+        }
+    }
+
+    // The materials are counted as loose EXP, so together with `exp` they
+    // must fit in one amount.
+    let mut total_exp = input.exp;
+    for (i, stack) in input.exp_items.iter_mut().enumerate() {
+        checker.clamp(format!("expItems[{}].exp", i), &mut stack.exp, 1..=i32::MAX)?;
+        checker.clamp(format!("expItems[{}].count", i), &mut stack.count, 0..=i32::MAX)?;
+        let fits = (i32::MAX - total_exp) / stack.exp;
+        if stack.count > fits {
+            checker.report(
+                format!("expItems[{}].count", i),
+                format!("{} items of {} EXP take the EXP total past {}", stack.count, stack.exp, i32::MAX),
+                fits,
+            )?;
+            stack.count = fits;
+        }
+        total_exp += stack.exp * stack.count;
+    }
+
+    if let Some(goal) = &mut input.goal {
+        for (i, target) in goal.pieces.iter_mut().enumerate() {
+            let field = |name: &str| format!("goal.pieces[{}].{}", i, name);
+            if let Some(gear_type) = target.gear_type.as_deref().filter(|t| !slots.iter().any(|slot| slot == t)) {
+                return Err(SolverError::validation(field("type"), format!("unknown gear type `{}`", gear_type)));
+            }
+            checker.clamp(field("enhancement"), &mut target.enhancement, enhancement_range.clone())?;
+            checker.clamp(field("mastery"), &mut target.mastery, mastery_range.clone())?;
+        }
+        for (i, target) in goal.scores.iter().enumerate() {
+            if !target.at_least.is_finite() {
+                return Err(SolverError::validation(format!("goal.scores[{}].atLeast", i), "must be a finite number"));
+            }
+        }
+        if let Some(weights) = &goal.cost_weights {
+            let values = [weights.exp, weights.hammers, weights.mythics, weights.mythril];
+            if values.iter().any(|w| !w.is_finite() || *w < 0.0) {
+                return Err(SolverError::validation("goal.costWeights", "weights must be non-negative numbers"));
+            }
+        }
+    }
+
+    if let Some(schedule) = &mut input.schedule {
+        if input.goal.is_some() {
+            return Err(SolverError::validation("schedule", "cannot be combined with a goal"));
+        }
+        checker.clamp("schedule.periods".to_string(), &mut schedule.periods, 1..=MAX_PERIODS)?;
+        checker.resources("schedule.income", &mut schedule.income)?;
+        // The plan is made for the budget plus the income of every period.
+        let later = schedule.periods - 1;
+        let budget = Resources { exp: input.exp, hammers: input.hammers, mythics: input.mythics, mythril: input.mythril };
+        for (name, income, held) in [
+            ("exp", &mut schedule.income.exp, budget.exp),
+            ("hammers", &mut schedule.income.hammers, budget.hammers),
+            ("mythics", &mut schedule.income.mythics, budget.mythics),
+            ("mythril", &mut schedule.income.mythril, budget.mythril),
+        ] {
+            if later > 0 && *income > (i32::MAX - held) / later {
+                let fits = (i32::MAX - held) / later;
+                checker.report(
+                    format!("schedule.income.{}", name),
+                    format!("{} over {} periods takes the budget past {}", income, later, i32::MAX),
+                    fits,
+                )?;
+                *income = fits;
+            }
+        }
+        // Plan steps name heroes, and the schedule maps them back.
+        for (i, hero) in input.heroes.iter().enumerate() {
+            if input.heroes[..i].iter().any(|other| other.name == hero.name) {
+                return Err(SolverError::validation(
+                    format!("heroes[{}].name", i),
+                    format!("`{}` is used twice; a schedule needs unique hero names", hero.name),
+                ));
+            }
+        }
+    }
+
+    // Buying every offer in full must not take the budget past what an amount
+    // can hold.
+    let mut most = Resources { exp: total_exp, ..input.budget() };
+    if let Some(shop) = &mut input.shop {
+        checker.clamp("shop.gems".to_string(), &mut shop.gems, 0..=i32::MAX)?;
+        for (i, offer) in shop.offers.iter_mut().enumerate() {
+            let field = |name: &str| format!("shop.offers[{}].{}", i, name);
+            checker.clamp(field("price"), &mut offer.price, 0..=i32::MAX)?;
+            checker.clamp(field("maxQuantity"), &mut offer.max_quantity, 0..=i32::MAX)?;
+            checker.resources(&field("contents"), &mut offer.contents)?;
+            let contents = offer.contents;
+            for (name, price, held) in [
+                ("gems", offer.price, 0),
+                ("exp", contents.exp, most.exp),
+                ("hammers", contents.hammers, most.hammers),
+                ("mythics", contents.mythics, most.mythics),
+                ("mythril", contents.mythril, most.mythril),
+            ] {
+                if price > 0 && offer.max_quantity > (i32::MAX - held) / price {
+                    let fits = (i32::MAX - held) / price;
+                    checker.report(
+                        field("maxQuantity"),
+                        format!("{} units take {} past {}", offer.max_quantity, name, i32::MAX),
+                        fits,
+                    )?;
+                    offer.max_quantity = fits;
+                }
+            }
+            most += offer.contents * offer.max_quantity;
+        }
+    }
+
+    let refund_percent = &mut input.reset_policy.refund_percent;
+    if !refund_percent.is_finite() || !(0.0..=100.0).contains(refund_percent) {
+        let fixed = if refund_percent.is_nan() { 100.0 } else { refund_percent.clamp(0.0, 100.0) };
+        checker.report(
+            "resetPolicy.refundPercent".to_string(),
+            format!("must be between 0 and 100, got {}", refund_percent),
+            fixed,
+        )?;
+        *refund_percent = fixed;
+    }
+
+    for (i, (hero, tiers)) in input.heroes.iter_mut().zip(&gear_tiers).enumerate() {
+        let field = |name: &str| format!("heroes[{}].{}", i, name);
+        for (name, weight) in [("lethality", &mut hero.weights.lethality), ("health", &mut hero.weights.health)] {
+            if !weight.is_finite() || *weight < 0.0 {
+                checker.report(
+                    field(&format!("weights.{}", name)),
+                    format!("must be a non-negative number, got {}", weight),
+                    0,
+                )?;
+                *weight = 0.0;
+            }
+        }
+        if let Some(gear_type) = hero.gear.0.keys().find(|key| !slots.contains(key)) {
+            return Err(SolverError::validation(
+                field(&format!("gear.{}", gear_type)),
+                format!("unknown gear type `{}`", gear_type),
+            ));
+        }
+        for gear_type in &slots {
+            if let Some(gear) = hero.gear.0.get_mut(gear_type) {
+                let prefix = field(&format!("gear.{}", gear_type));
+                let (_, tier) = tiers.iter().find(|(name, _)| name == gear_type).expect("every piece has a tier");
+                let (enhancement_range, mastery_range) = &tier_ranges[*tier];
+                checker.gear(&prefix, gear, enhancement_range, mastery_range)?;
+            }
+        }
+    }
+
+    Ok(checker.warnings)
+}
+
+struct Checker {
+    mode: ValidationMode,
+    warnings: Vec<ValidationWarning>,
+}
+
+impl Checker {
+    /// Fails in strict mode; in lenient mode records that `field` is being
+    /// replaced by `fixed`.
+    fn report(&mut self, field: String, problem: String, fixed: impl std::fmt::Display) -> Result<(), SolverError> {
+        match self.mode {
+            ValidationMode::Strict => Err(SolverError::validation(field, problem)),
+            ValidationMode::Lenient => {
+                self.warnings.push(ValidationWarning { field, message: format!("{}; using {}", problem, fixed) });
+                Ok(())
+            }
+        }
+    }
+
+    fn clamp(&mut self, field: String, value: &mut i32, range: RangeInclusive<i32>) -> Result<(), SolverError> {
+        if range.contains(value) {
+            return Ok(());
+        }
+        let fixed = (*value).clamp(*range.start(), *range.end());
+        let problem = if *range.end() == i32::MAX && *range.start() == 0 {
+            format!("must not be negative, got {}", value)
+        } else if *range.end() == i32::MAX {
+            format!("must be at least {}, got {}", range.start(), value)
+        } else {
+            format!("{} is outside {}..={}", value, range.start(), range.end())
+        };
+        self.report(field, problem, fixed)?;
+        *value = fixed;
+        Ok(())
+    }
+
+    fn resources(&mut self, prefix: &str, resources: &mut Resources) -> Result<(), SolverError> {
+        for (name, amount) in [
+            ("exp", &mut resources.exp),
+            ("hammers", &mut resources.hammers),
+            ("mythics", &mut resources.mythics),
+            ("mythril", &mut resources.mythril),
+        ] {
+            self.clamp(format!("{}.{}", prefix, name), amount, 0..=i32::MAX)?;
+        }
+        Ok(())
+    }
+
+    fn gear(
+        &mut self,
+        prefix: &str,
+        gear: &mut Gear,
+        enhancement_range: &RangeInclusive<i32>,
+        mastery_range: &RangeInclusive<i32>,
+    ) -> Result<(), SolverError> {
+        let field = |name: &str| format!("{}.{}", prefix, name);
+        self.clamp(field("enhancement"), &mut gear.enhancement, enhancement_range.clone())?;
+        self.clamp(field("mastery"), &mut gear.mastery, mastery_range.clone())?;
+        for (name, value, range) in [
+            ("minEnhancement", &mut gear.min_enhancement, enhancement_range),
+            ("maxEnhancement", &mut gear.max_enhancement, enhancement_range),
+            ("targetMastery", &mut gear.target_mastery, mastery_range),
+        ] {
+            if let Some(value) = value {
+                self.clamp(field(name), value, range.clone())?;
+            }
+        }
+        if let (Some(min), Some(max)) = (gear.min_enhancement, gear.max_enhancement) {
+            if min > max {
+                self.report(
+                    field("minEnhancement"),
+                    format!("{} is above maxEnhancement {}", min, max),
+                    max,
+                )?;
+                gear.min_enhancement = Some(max);
+            }
+        }
+        Ok(())
+    }
+}
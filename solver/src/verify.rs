@@ -0,0 +1,130 @@
+use crate::error::SolverError;
+use crate::model::{can_reset, enhancement_cost, mastery_cost};
+use crate::reset::may_reset;
+use crate::types::*;
+use crate::validate::validate_input;
+
+/// Checks a proposed final gear state for the heroes of `input` against the
+/// game's rules, the pieces' constraints and the budget, and adds up what it
+/// costs. A slot missing from a hero's proposed gear stays as it is and is
+/// checked like any other, so it must already meet its floors. A piece
+/// proposed below its current enhancement is reset first, and one proposed
+/// in a higher tier is crafted.
+pub fn verify_plan(input: &InputData, plan: &[HeroGear]) -> Result<PlanCheck, SolverError> {
+    let mut input = input.clone();
+    validate_input(&mut input)?;
+    let game_data = input.game_data();
+    let mut check = PlanCheck::default();
+    let mut violation = |field: String, message: String| check.violations.push(PlanViolation { field, message });
+
+    if plan.len() != input.heroes.len() {
+        violation("heroes".to_string(), format!("{} heroes but {} proposed gear sets", input.heroes.len(), plan.len()));
+    }
+    let mut required = Resources::default();
+    let mut reclaimed_exp: i32 = 0;
+    for (i, (hero, proposed)) in input.heroes.iter().zip(plan).enumerate() {
+        for key in proposed.0.keys().filter(|key| game_data.slot_index(key).is_none()) {
+            violation(format!("heroes[{}].gear.{}", i, key), "is not a slot of the gear set".to_string());
+        }
+        for (slot, current) in game_data.gear_set.slots.iter().zip(game_data.pieces(&hero.gear)) {
+            let target = proposed.0.get(&slot.name).unwrap_or(current);
+            let field = |name: &str| format!("heroes[{}].gear.{}.{}", i, slot.name, name);
+            let from_tier = game_data.tier_rank(current.tier.as_deref()).unwrap_or(game_data.top_tier());
+            let Some(tier) = game_data.tier_rank(target.tier.as_deref()) else {
+                violation(field("tier"), format!("unknown tier `{}`", target.tier.as_deref().unwrap_or_default()));
+                continue;
+            };
+            let tables = game_data.tables(tier);
+
+            let crafted = tier > from_tier;
+            if tier < from_tier {
+                violation(field("tier"), "cannot go down a tier".to_string());
+                continue;
+            }
+            if crafted {
+                match tables.craft_cost {
+                    Some(cost) if !current.locked && !current.no_reset => required += cost,
+                    Some(_) => {
+                        violation(field("tier"), "crafting would give up a locked or noReset piece".to_string());
+                        continue;
+                    }
+                    None => {
+                        violation(field("tier"), format!("`{}` cannot be crafted", target.tier.as_deref().unwrap_or_default()));
+                        continue;
+                    }
+                }
+            }
+            let (mut from_enhancement, from_mastery) =
+                if crafted { (0, 0) } else { (current.enhancement, current.mastery) };
+
+            if !(0..=tables.max_enhancement).contains(&target.enhancement) {
+                violation(field("enhancement"), format!("{} is outside 0..={}", target.enhancement, tables.max_enhancement));
+                continue;
+            }
+            if !(0..=tables.max_mastery).contains(&target.mastery) {
+                violation(field("mastery"), format!("{} is outside 0..={}", target.mastery, tables.max_mastery));
+                continue;
+            }
+            if current.locked && (target.enhancement, target.mastery) != (current.enhancement, current.mastery) {
+                violation(field("enhancement"), "is locked".to_string());
+                continue;
+            }
+            if target.mastery < from_mastery {
+                violation(field("mastery"), format!("cannot go down from {} to {}", from_mastery, target.mastery));
+                continue;
+            }
+            if target.enhancement < from_enhancement {
+                if !can_reset(current) {
+                    violation(field("enhancement"), format!("going down to {} needs a reset, which the piece does not allow", target.enhancement));
+                    continue;
+                }
+                if !may_reset(&input.reset_policy, game_data, current) {
+                    violation(field("enhancement"), format!("going down to {} needs a reset, which the reset policy forbids", target.enhancement));
+                    continue;
+                }
+                reclaimed_exp = reclaimed_exp.saturating_add(input.reset_policy.refund(tables, current.enhancement));
+                from_enhancement = 0;
+            }
+
+            // A crafted piece keeps the player's constraints but starts at 0.
+            if let Some(min) = current.min_enhancement.filter(|&min| target.enhancement < min) {
+                violation(field("enhancement"), format!("{} is below minEnhancement {}", target.enhancement, min));
+            }
+            if let Some(max) = current.max_enhancement.filter(|&max| target.enhancement > max.max(if crafted { 0 } else { current.enhancement })) {
+                violation(field("enhancement"), format!("{} is above maxEnhancement {}", target.enhancement, max));
+            }
+            if let Some(mastery) = current.target_mastery.map(|m| m.max(from_mastery)).filter(|&m| target.mastery != m) {
+                violation(field("mastery"), format!("{} is not targetMastery {}", target.mastery, mastery));
+            }
+            let needed = tables.required_mastery(target.enhancement);
+            if target.enhancement > from_enhancement && target.mastery < needed {
+                violation(field("mastery"), format!("enhancement {} needs mastery {}", target.enhancement, needed));
+            }
+            required += enhancement_cost(tables, from_enhancement, target.enhancement)
+                + mastery_cost(tables, from_mastery, target.mastery);
+        }
+    }
+
+    let budget = input.budget() + Resources { exp: reclaimed_exp, ..Resources::default() };
+    let shortfall = Resources {
+        exp: (required.exp - budget.exp).max(0),
+        hammers: (required.hammers - budget.hammers).max(0),
+        mythics: (required.mythics - budget.mythics).max(0),
+        mythril: (required.mythril - budget.mythril).max(0),
+    };
+    for (name, missing) in [
+        ("exp", shortfall.exp),
+        ("hammers", shortfall.hammers),
+        ("mythics", shortfall.mythics),
+        ("mythril", shortfall.mythril),
+    ] {
+        if missing > 0 {
+            violation(name.to_string(), format!("the plan needs {} more", missing));
+        }
+    }
+    check.feasible = check.violations.is_empty();
+    check.required = required;
+    check.reclaimed_exp = reclaimed_exp;
+    check.shortfall = shortfall;
+    Ok(check)
+}
@@ -1,7 +1,20 @@
 import { Injectable, signal } from '@angular/core';
 
 // Import the initializer and the function you want to use
-import init, { solve } from 'solver';
+import init, { cost, solve, solve_batch, verify } from 'solver';
+
+export interface SolverErrorDetails {
+  kind: 'parse' | 'validation' | 'internal';
+  field: string | null;
+  message: string;
+}
+
+export class SolverError extends Error {
+  constructor(public readonly details: SolverErrorDetails) {
+    super(details.field ? `${details.field}: ${details.message}` : details.message);
+    this.name = 'SolverError';
+  }
+}
 
 @Injectable({
   providedIn: 'root'
@@ -24,10 +37,37 @@ export class SolverService {
   }
 
   solve(data: any): any {
+    const output = this.call<any>(solve, data);
+    for (const warning of output.warnings ?? []) {
+      console.warn(`Solver input adjusted: ${warning.field}: ${warning.message}`);
+    }
+    return output;
+  }
+
+  /** Solves `{players: [{name, input}]}` in one call; each player's `result` is its own ok/error envelope. */
+  solveBatch(data: any): any {
+    return this.call(solve_batch, data);
+  }
+
+  /** Checks `{input, gear}`, a final gear object per hero, and returns `{feasible, required, violations, ...}`. */
+  verifyPlan(data: any): any {
+    return this.call(verify, data);
+  }
+
+  /** Prices `{from, to}`, two states of one piece, as `{cost, mastery, gates}`. */
+  upgradeCost(data: any): any {
+    return this.call(cost, data);
+  }
+
+  /** Runs a solver entry point on `data` and unwraps its `{ok}` or `{error}` envelope. */
+  private call<T>(entry: (json: string) => string, data: unknown): T {
     if (!this.isSolverLoaded()) {
       throw new Error('Solver not yet loaded.');
     }
-    // The 'solve' function is now directly available
-    return JSON.parse(solve(JSON.stringify(data)));
+    const result = JSON.parse(entry(JSON.stringify(data)));
+    if (result.error) {
+      throw new SolverError(result.error);
+    }
+    return result.ok as T;
   }
 }